@@ -0,0 +1,55 @@
+//! Measures allocations and time for parsing a large command line.
+//!
+//! Run with `cargo bench`.
+
+use simple_cli_parser::{Arg, Parser};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const ITERATIONS: u32 = 20;
+
+fn main() {
+    // 500 inputs with a value each gives a 1000 token command line
+    let parser = Parser::new();
+    let mut tokens = vec![];
+    for i in 0..500 {
+        let name = format!("opt{}", i);
+        tokens.push(format!("--{}", name));
+        tokens.push(format!("value{}", i));
+        parser.add_arg(Arg::new().input(&name));
+    }
+
+    let mut total_allocations = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let input = tokens.clone();
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let output = parser.try_get_matches_from(input).unwrap();
+        total_allocations += ALLOCATIONS.load(Ordering::Relaxed) - before;
+        assert_eq!(output.len(), 500);
+    }
+    let elapsed = start.elapsed();
+
+    println!("parse 1000 tokens: {} allocations, {:?} per parse",
+        total_allocations / ITERATIONS as usize,
+        elapsed / ITERATIONS);
+}
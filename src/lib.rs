@@ -9,8 +9,8 @@
 //! [`Parser::add_arg()`] or [`Parser::add_args()`] methods.
 //! Then, call the [`Parser::parse()`] method with [`std::env::Args`] passed in.
 //! 
-//! ```
-//! use cli_parser::{ Parser, Arg };
+//! ```no_run
+//! use simple_cli_parser::{ Parser, Arg };
 //! use std::env;
 //! 
 //! fn main() {
@@ -30,498 +30,7359 @@
 //!     }
 //! }
 //! ```
+//! 
+//! ## Features
+//! - `serde`: implements `serde::Serialize` for [`ParseOutput`] and adds `ParseOutput::to_json()`.
 
-use std::cell::RefCell;
+use std::cell::{Cell, Ref, RefCell};
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Display;
+use std::io::Read;
+use std::iter::Peekable;
+use std::rc::Rc;
+use std::time::Duration;
 
-#[derive(Debug)]
-struct InvalidCommandError {
-    reason: InvalidCommandReasons
+/// The reason a command failed to parse.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// A token was not recognized or was not allowed where it appeared.
+    Unexpected(String),
+    /// A required argument was not provided.
+    Missing,
+    /// Required arguments were not provided, holding their names.
+    MissingRequired(Vec<String>),
+    /// An argument was provided more than once.
+    Duplicate(String),
+    /// An option appeared after a positional while [`Parser::strict_ordering()`] was enabled.
+    OptionAfterPositional(String),
+    /// More positional arguments were provided than there are params.
+    TooManyPositionals { expected: usize, got: usize },
+    /// Fewer positional arguments were provided than there are params.
+    TooFewPositionals { expected: usize, got: usize },
+    /// An input never received its value, because it was the last token or was followed by an unquoted `--`.
+    MissingValue(String),
+    /// The value given to an argument was rejected, with `kind` describing why.
+    Value { name: String, value: String, kind: ValueErrorKind },
+    /// More than one argument was registered with the same short char, so it can't be matched reliably.
+    AmbiguousShort(char),
+    /// An argument set with [`Arg::requires_any()`] was provided without any of the arguments it requires.
+    MissingRequirement { arg: String, requires: Vec<String> },
+    /// More tokens were provided than [`Parser::max_args()`] allows.
+    TooManyArgs { limit: usize },
+    /// A group set with [`Parser::exclusive_group()`] had the wrong number of its members given, with `reason` saying which way.
+    GroupViolation { members: Vec<String>, reason: GroupViolationKind },
+    /// Only some members of a group set with [`Parser::group_all_or_none()`] were given.
+    PartialGroup { group: Vec<String>, present: Vec<String>, missing: Vec<String> },
+    /// The first token wasn't one of the modes set with [`Parser::mode()`].
+    InvalidMode { got: String, allowed: Vec<String> },
 }
 
-impl InvalidCommandError {
-    pub fn new(reason: InvalidCommandReasons) -> InvalidCommandError {
-        InvalidCommandError { reason }
+impl ParseError {
+    /// Returns a stable numeric code for the reason parsing failed, e.g. to use as an exit code.
+    /// 
+    /// | Code | Reason |
+    /// |------|--------|
+    /// | 1 | [`ParseError::Unexpected`] |
+    /// | 2 | [`ParseError::Missing`] |
+    /// | 3 | [`ParseError::Duplicate`] |
+    /// | 4 | [`ParseError::OptionAfterPositional`] |
+    /// | 5 | [`ParseError::TooManyPositionals`] |
+    /// | 6 | [`ParseError::TooFewPositionals`] |
+    /// | 7 | [`ParseError::MissingValue`] |
+    /// | 8 | [`ParseError::Value`] |
+    /// | 9 | [`ParseError::AmbiguousShort`] |
+    /// | 10 | [`ParseError::MissingRequired`] |
+    /// | 11 | [`ParseError::MissingRequirement`] |
+    /// | 12 | [`ParseError::TooManyArgs`] |
+    /// | 13 | [`ParseError::GroupViolation`] |
+    /// | 14 | [`ParseError::PartialGroup`] |
+    /// | 15 | [`ParseError::InvalidMode`] |
+    /// 
+    /// Codes are never reused or changed, and new reasons are given the next free code.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("verbose"));
+    /// 
+    /// let err = parser.try_get_matches_from(["--nope"]).unwrap_err();
+    /// assert_eq!(err.code(), 1);
+    /// ```
+    pub fn code(&self) -> u8 {
+        match self {
+            ParseError::Unexpected(_) => 1,
+            ParseError::Missing => 2,
+            ParseError::Duplicate(_) => 3,
+            ParseError::OptionAfterPositional(_) => 4,
+            ParseError::TooManyPositionals { .. } => 5,
+            ParseError::TooFewPositionals { .. } => 6,
+            ParseError::MissingValue(_) => 7,
+            ParseError::Value { .. } => 8,
+            ParseError::AmbiguousShort(_) => 9,
+            ParseError::MissingRequired(_) => 10,
+            ParseError::MissingRequirement { .. } => 11,
+            ParseError::TooManyArgs { .. } => 12,
+            ParseError::GroupViolation { .. } => 13,
+            ParseError::PartialGroup { .. } => 14,
+            ParseError::InvalidMode { .. } => 15,
+        }
     }
 }
 
-impl Display for InvalidCommandError {
+impl Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match &self.reason {
-            InvalidCommandReasons::Unexpected(s) => {
+        match self {
+            ParseError::Unexpected(s) => {
                 write!(f, "Invalid command, unexpected token '{}'", s)
             },
-            InvalidCommandReasons::Duplicate(s) => {
+            ParseError::Duplicate(s) => {
                 write!(f, "Invalid command, duplicate token '{}'", s)
             },
-            InvalidCommandReasons::Missing => {
+            ParseError::Missing => {
                 write!(f, "Invalid command, missing argument")
+            },
+            ParseError::MissingRequired(names) => {
+                let names: Vec<String> = names.iter().map(|name| format!("'{}'", name)).collect();
+                write!(f, "Invalid command, missing required arguments {}", names.join(", "))
+            },
+            ParseError::OptionAfterPositional(s) => {
+                write!(f, "Invalid command, option '{}' must come before any positional arguments", s)
+            },
+            ParseError::TooManyPositionals { expected, got } => {
+                write!(f, "Invalid command, too many positional arguments: expected {}, got {}", expected, got)
+            },
+            ParseError::TooFewPositionals { expected, got } => {
+                write!(f, "Invalid command, missing positional arguments: expected {}, got {}", expected, got)
+            },
+            ParseError::MissingValue(s) => {
+                write!(f, "Invalid command, missing value for '{}'", s)
+            },
+            ParseError::Value { name, value, kind } => {
+                write!(f, "Invalid command, invalid value '{}' for '{}': {}", value, name, kind)
+            },
+            ParseError::AmbiguousShort(c) => {
+                write!(f, "Invalid parser, short option '-{}' is used by more than one argument", c)
+            },
+            ParseError::MissingRequirement { arg, requires } => {
+                let names: Vec<String> = requires.iter().map(|name| format!("'{}'", name)).collect();
+                write!(f, "Invalid command, '{}' requires one of {}", arg, names.join(", "))
+            },
+            ParseError::TooManyArgs { limit } => {
+                write!(f, "Invalid command, more than {} arguments", limit)
+            },
+            ParseError::GroupViolation { members, reason } => {
+                let names: Vec<String> = members.iter().map(|name| format!("'{}'", name)).collect();
+                match reason {
+                    GroupViolationKind::NoneSelected => write!(f, "Invalid command, one of {} is required", names.join(", ")),
+                    GroupViolationKind::TooMany => write!(f, "Invalid command, only one of {} can be given", names.join(", ")),
+                }
+            },
+            ParseError::PartialGroup { present, missing, .. } => {
+                let present: Vec<String> = present.iter().map(|name| format!("'{}'", name)).collect();
+                let missing: Vec<String> = missing.iter().map(|name| format!("'{}'", name)).collect();
+                write!(f, "Invalid command, {} must be given with {}", present.join(", "), missing.join(", "))
+            },
+            ParseError::InvalidMode { got, allowed } => {
+                let names: Vec<String> = allowed.iter().map(|name| format!("'{}'", name)).collect();
+                write!(f, "Invalid command, unknown mode '{}', expected one of {}", got, names.join(", "))
             }
         }
     }
 }
 
-impl Error for InvalidCommandError {
+impl Error for ParseError {
     
 }
 
-#[derive(Debug)]
-enum InvalidCommandReasons {
-    Unexpected(String),
-    Missing,
-    Duplicate(String),
+/// Why the value of an argument was rejected, as part of [`ParseError::Value`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueErrorKind {
+    /// The value couldn't be converted to the type the argument expects, e.g. an invalid [`Arg::byte_size()`].
+    Type(&'static str),
+    /// The value was empty, but the argument was set with [`Arg::non_empty()`].
+    Empty,
+    /// The key of a `key=value` pair isn't in the schema set with [`Arg::define_schema()`], and unknown keys were denied.
+    UnknownKey,
+    /// The value isn't one of those allowed by [`Arg::toggle()`], which are held here.
+    NotAllowed(Vec<String>),
 }
 
-#[derive(Clone, Debug)]
-enum ArgTypes {
-    Param(bool),
-    Input,
+impl Display for ValueErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ValueErrorKind::Type(t) => {
+                write!(f, "expected {}", t)
+            },
+            ValueErrorKind::Empty => {
+                write!(f, "the value can't be empty")
+            },
+            ValueErrorKind::UnknownKey => {
+                write!(f, "the key is not known")
+            },
+            ValueErrorKind::NotAllowed(allowed) => {
+                let allowed: Vec<String> = allowed.iter().map(|value| format!("'{}'", value)).collect();
+                write!(f, "expected one of {}", allowed.join(", "))
+            }
+        }
+    }
+}
+
+/// Why the members of a group broke its rule, as part of [`ParseError::GroupViolation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupViolationKind {
+    /// None of the members were given, but the group is required.
+    NoneSelected,
+    /// More than one of the members was given.
+    TooMany,
+}
+
+/// A problem with how a [`Parser`] was configured, found by [`Parser::validate()`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// More than one argument can be called by this long name or alias.
+    DuplicateName(String),
+    /// More than one argument uses this short char.
+    DuplicateShort(char),
+    /// An argument refers to another argument which isn't registered, e.g. through [`Arg::implies()`].
+    UnknownReference { arg: String, reference: String },
+    /// An argument's name is also the namespace of a dotted name, like `db` alongside `db.host`.
+    NamespaceCollision(String),
+    /// More than one argument is stored under this key, e.g. through [`Arg::id()`], so their values would overwrite each other.
+    DuplicateKey(String),
+    /// A group of arguments, made with [`Parser::exclusive_group()`] or [`Parser::group_all_or_none()`], names an argument which isn't registered.
+    UnknownMember { group: Vec<String>, member: String },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConfigError::DuplicateName(s) => {
+                write!(f, "Invalid parser, the name '{}' is used by more than one argument", s)
+            },
+            ConfigError::DuplicateShort(c) => {
+                write!(f, "Invalid parser, short option '-{}' is used by more than one argument", c)
+            },
+            ConfigError::UnknownReference { arg, reference } => {
+                write!(f, "Invalid parser, '{}' refers to unknown argument '{}'", arg, reference)
+            },
+            ConfigError::NamespaceCollision(s) => {
+                write!(f, "Invalid parser, the name '{}' is also used as a namespace", s)
+            },
+            ConfigError::DuplicateKey(s) => {
+                write!(f, "Invalid parser, more than one argument is stored under the key '{}'", s)
+            },
+            ConfigError::UnknownMember { group, member } => {
+                let names: Vec<String> = group.iter().map(|name| format!("'{}'", name)).collect();
+                write!(f, "Invalid parser, the group {} refers to unknown argument '{}'", names.join(", "), member)
+            },
+        }
+    }
+}
+
+impl Error for ConfigError {
+
+}
+
+/// Something suspicious about a command which parsed successfully, found by [`Parser::parse_with_warnings()`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseWarning {
+    /// An input took a value which could have filled a missing param instead.
+    ValueMayBePositional { arg: String, value: String },
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseWarning::ValueMayBePositional { arg, value } => {
+                write!(f, "'{}' was taken as the value of '{}', but a positional argument is missing", value, arg)
+            }
+        }
+    }
+}
+
+/// How a single token was interpreted, as reported by [`Parser::explain()`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Classification {
+    /// An option called by its long name or an alias, holding the argument's name.
+    LongOption(String),
+    /// An option called by its short char.
+    ShortOption(char),
+    /// The value of an input or trailing arg, holding the argument's name.
+    Value(String),
+    /// A positional filling a param, holding the param's name.
+    Positional(String),
+    /// The `--` options terminator, or the end of the values of an [`Arg::terminator()`].
+    Terminator,
+    /// The mode chosen with [`Parser::mode()`].
+    Mode,
+    /// A token that doesn't match anything.
+    Unknown,
+}
+
+/// Counts of what happened while parsing, returned by [`ParseOutput::stats()`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    /// The number of tokens parsed.
+    pub tokens: usize,
+    /// The number of times a registered option was called, counting each short in a cluster.
+    pub options: usize,
+    /// The number of positionals which filled a param.
+    pub positionals: usize,
+    /// The number of unknown options collected by [`Parser::catch_all()`] instead of failing.
+    pub unknowns: usize,
+}
+
+/// How an option was spelled when it was called, returned by [`ParseOutput::matched_form()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchedForm {
+    /// The long name, like `--verbose`.
+    Long,
+    /// The short char, like `-v`, either alone or in a cluster.
     Short(char),
-    None
+    /// An alias, holding the alias used.
+    Alias(String),
 }
 
-/// Represents a single argument which can be passed to a [`Parser`].
+/// The arguments found by [`Parser::try_get_matches_from()`] and their values.
 /// 
 /// # Example
 /// ```
-/// let arg = Arg::new().param("num");
+/// # use simple_cli_parser::{Arg, Parser};
 /// let parser = Parser::new();
-/// let mut args = std::env::args();
-/// args.next();
+/// parser.add_args(vec![Arg::new().flag("help").short('h'), Arg::new().input("out")]);
 /// 
-/// parser.add_arg(arg);
-/// let output = parser.parse(&mut args).unwrap();
+/// let output = parser.try_get_matches_from(["-h", "--out", "file.txt"]).unwrap();
+/// assert!(output.is_present("help"));
+/// assert_eq!(output.value_of("out"), Some("file.txt"));
 /// ```
-#[derive(Clone, Debug)]
-pub struct Arg {
-    name: String,
-    arg_type: ArgTypes,
-    expecting: bool,
+#[derive(Clone, Debug, Default)]
+pub struct ParseOutput {
+    values: HashMap<String, Option<String>>,
+    lists: HashMap<String, Vec<String>>,
+    counts: HashMap<String, u32>,
+    defines: HashMap<String, HashMap<String, String>>,
+    catch_all: Option<String>,
+    warnings: Vec<ParseWarning>,
+    bin_name: Option<String>,
+    filled: Vec<String>,
+    args: Vec<Arg>,
+    trailing: Option<String>,
+    mode: bool,
+    stats: ParseStats,
+    forms: HashMap<String, MatchedForm>,
 }
 
-impl Arg {
-    /// Create a new arg object, note you must call further methods on this for it to be useful.
-    /// 
-    /// # Example
-    /// ```
-    /// let arg = Arg::new();
-    /// ```
-    pub fn new() -> Arg {
-        
-        Arg { name: String::new(), arg_type: ArgTypes::None, expecting: false} 
+// Stats and matched forms describe how a command was parsed rather than what it holds, so they don't affect equality,
+// and neither do the parser's arguments, which are only kept to build the command line when it's asked for
+impl PartialEq for ParseOutput {
+    fn eq(&self, other: &Self) -> bool {
+        self.values == other.values
+            && self.lists == other.lists
+            && self.counts == other.counts
+            && self.defines == other.defines
+            && self.catch_all == other.catch_all
+            && self.warnings == other.warnings
+            && self.bin_name == other.bin_name
+            && self.filled == other.filled
     }
+}
 
-    /// A parameter argument, or one that does not expect any argument to come before it.
-    /// Note that the order that these are added to the parser matters.
-    /// 
-    /// # Example
-    /// ```
-    /// let arg = Arg::new().param("p1");
-    /// ```
-    /// `arg` is a required argument and the [`Parser::parse()`] will return an error if it is not present.
-    pub fn param(self, name: &str) -> Arg {
-        Arg { name: String::from(name), arg_type: ArgTypes::Param(false), expecting: false }
+impl ParseOutput {
+    /// Returns `true` if the argument was provided.
+    pub fn is_present(&self, name: &str) -> bool {
+        self.values.contains_key(name) || self.lists.contains_key(name) || self.defines.contains_key(name)
     }
 
-    /// An optional argument that expects a value to follow directly after it.
+    /// Returns `true` if the flag was provided and not disabled with `-name` by [`Parser::plus_flags()`].
+    /// For inputs and params this is the same as [`ParseOutput::is_present()`].
     /// 
     /// # Example
     /// ```
-    /// let arg = Arg::new().input("inp");
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("color"), Arg::new().flag("verbose")]);
+    /// parser.plus_flags(true);
+    /// 
+    /// let output = parser.try_get_matches_from(["-color"]).unwrap();
+    /// assert!(!output.get_flag("color"));
+    /// assert!(!output.get_flag("verbose"));
     /// ```
-    /// Upon parsing, if `--inp` is one of the arguments called, `arg` will be in the output with whatever string comes next in the arguments.
-    pub fn input(self, name: &str) -> Arg {
-        Arg { name: String::from(name), arg_type: ArgTypes::Input, expecting: true }
+    pub fn get_flag(&self, name: &str) -> bool {
+        self.is_present(name) && self.value_of(name) != Some("false")
     }
 
-    /// A flag argument, or one that toggles a setting without expecting another token afterwards.
+    /// Returns the value of the argument, or `None` if it was not provided or doesn't take a value.
+    pub fn value_of(&self, name: &str) -> Option<&str> {
+        self.values.get(name)?.as_deref()
+    }
+
+    /// Returns all values collected for the argument, like the trailing args set by [`Parser::trailing_var_arg()`].
+    pub fn values_of(&self, name: &str) -> Option<&[String]> {
+        self.lists.get(name).map(Vec::as_slice)
+    }
+
+    /// Returns the positionals grouped by an [`Arg::takes_positionals()`] flag, or an empty list if it was not provided.
+    pub fn group_of(&self, name: &str) -> Vec<String> {
+        self.lists.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Returns a normalized command line which parses to the same result, with values quoted where needed.
     /// 
     /// # Example
     /// ```
-    /// let arg = Arg::new().flag("optional");
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().param("file"), Arg::new().input("msg").short('m'), Arg::new().flag("verbose")]);
+    /// 
+    /// let output = parser.try_get_matches_from(["a.txt", "-m=it's done", "--verbose"]).unwrap();
+    /// assert_eq!(output.to_command_line(), r"--msg 'it'\''s done' --verbose a.txt");
+    /// assert_eq!(parser.parse_str(&output.to_command_line()), Ok(output));
     /// ```
-    /// Upon parsing, if `--optional` is one of the arguments called, `arg` will be in the output with the value `true`.
-    pub fn flag(self, name: &str) -> Arg {
-        Arg { name: String::from(name), arg_type: self.arg_type, expecting: false }
+    /// Options are listed by their long name in the order they were added, followed by the positionals.
+    /// Flags toggled with [`Parser::plus_flags()`] keep their `+name` or `-name` form.
+    /// Defaults and implied arguments are included as if they were given.
+    pub fn to_command_line(&self) -> String {
+        let mut tokens: Vec<String> = vec![];
+        if let Some(Some(mode)) = self.values.get("mode").filter(|_| self.mode) {
+            tokens.push(shell_escape(mode));
+        }
+        // Values that look like options are attached so they can't be read as one
+        let push_value = |tokens: &mut Vec<String>, option: &str, value: &str| {
+            if value.starts_with('-') {
+                tokens.push(shell_escape(&format!("{}={}", option, value)));
+            } else {
+                tokens.push(String::from(option));
+                tokens.push(shell_escape(value));
+            }
+        };
+        let sorted = |pairs: &HashMap<String, String>| {
+            let mut pairs: Vec<(String, String)> = pairs.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+            pairs.sort();
+            pairs
+        };
+
+        for arg in self.args.iter().filter(|arg| !matches!(arg.arg_type, ArgTypes::Param(_))) {
+            let option = arg.long_option();
+            if let Some(pairs) = self.defines.get(arg.key()) {
+                for (key, value) in sorted(pairs) {
+                    push_value(&mut tokens, &option, &format!("{}={}", key, value));
+                }
+            } else if let Some(values) = self.lists.get(arg.key()) {
+                if let Some(delimiter) = arg.delimiter {
+                    let mut joined = values.join(&delimiter.to_string());
+                    if values.last().is_some_and(String::is_empty) {
+                        joined.push(delimiter);
+                    }
+                    push_value(&mut tokens, &option, &joined);
+                } else {
+                    tokens.push(option);
+                    tokens.extend(values.iter().map(|value| shell_escape(value)));
+                    if let Some(terminator) = &arg.terminator {
+                        // Left unquoted, since a quoted terminator is read as a value
+                        tokens.push(terminator.clone());
+                    }
+                }
+            } else if let Some(value) = self.values.get(arg.key()) {
+                match value {
+                    None => {
+                        let count = self.counts.get(arg.key()).copied().unwrap_or(1);
+                        tokens.extend((0..count).map(|_| option.clone()));
+                    },
+                    Some(value) if arg.expecting => push_value(&mut tokens, &option, value),
+                    // Flags with a value were toggled by `Parser::plus_flags()`, so they keep that form
+                    Some(value) if value == "false" => tokens.push(format!("-{}", arg.name)),
+                    Some(_) => tokens.push(format!("+{}", arg.name)),
+                }
+            }
+        }
+
+        if let Some(extras) = self.catch_all.as_ref().and_then(|name| self.defines.get(name)) {
+            for (key, value) in sorted(extras) {
+                tokens.push(shell_escape(&format!("--{}={}", key, value)));
+            }
+        }
+
+        let positionals: Vec<&str> = self.args.iter()
+            .filter(|arg| matches!(arg.arg_type, ArgTypes::Param(true)))
+            .filter_map(|arg| self.values.get(arg.key())?.as_deref())
+            .collect();
+        let trailing = self.trailing.as_ref().and_then(|name| self.lists.get(name));
+        if trailing.is_none() && positionals.iter().any(|value| value.starts_with('-')) {
+            tokens.push(String::from("--"));
+        }
+        tokens.extend(positionals.into_iter().map(shell_escape));
+        if let Some(values) = trailing {
+            tokens.push(String::from("--"));
+            tokens.extend(values.iter().map(|value| shell_escape(value)));
+        }
+
+        tokens.join(" ")
     }
 
-    /// Sets a short option for the argument, allowing it to be called with a char rather than a string.
+    /// Returns how many positionals were given, each of which filled a param.
     /// 
     /// # Example
     /// ```
-    /// let arg = Arg::new().flag("help").short('h');
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().param("a"), Arg::new().param("b"), Arg::new().param("c")]);
+    /// parser.require_params(false);
+    /// 
+    /// let output = parser.try_get_matches_from(["1.txt", "2.txt"]).unwrap();
+    /// assert_eq!(output.positional_count(), 2);
+    /// assert_eq!(output.positional_names(), ["a", "b"]);
     /// ```
-    /// The `arg` variable can be called by `--help` or by `-h`.
-    pub fn short(self, ch: char) -> Arg {
-        Arg { name: self.name, arg_type: ArgTypes::Short(ch), expecting: self.expecting }
+    pub fn positional_count(&self) -> usize {
+        self.filled.len()
     }
 
-    fn set_used(&mut self, used: bool) {
-        self.arg_type = ArgTypes::Param(used);
+    /// Returns the names of the params filled by positionals, in the order they were filled.
+    pub fn positional_names(&self) -> &[String] {
+        &self.filled
     }
-}
 
-/// A struct that parses the command line for certain [`Arg`]s.
-/// 
-/// # Example
-/// ```
-/// let parser = Parser::new();
-/// let arg = Arg::new().param("num");
-/// let mut args = std::env::args();
-/// args.next();
-/// 
-/// parser.add_arg(arg);
-/// let output = parser.parse(&mut args).unwrap();
-/// ```
-pub struct Parser {
-    args: RefCell<Vec<Arg>>,
-}
+    /// Returns the value of an [`Arg::byte_size()`] argument in bytes.
+    pub fn byte_size_of(&self, name: &str) -> Option<u64> {
+        self.value_of(name)?.parse().ok()
+    }
 
-impl Parser {
+    /// Returns the value of an [`Arg::duration()`] argument.
+    pub fn duration_of(&self, name: &str) -> Option<Duration> {
+        self.value_of(name)?.parse().ok().map(Duration::from_millis)
+    }
 
-    /// Creates a new Parser struct.
+    /// Returns the `key=value` pairs collected by an [`Arg::defines()`] argument.
+    pub fn defines_of(&self, name: &str) -> Option<&HashMap<String, String>> {
+        self.defines.get(name)
+    }
+
+    /// Returns the unknown long options collected by [`Parser::catch_all()`], keyed by their long name.
+    pub fn extras(&self) -> HashMap<String, String> {
+        self.catch_all.as_ref()
+            .and_then(|name| self.defines.get(name))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns the values of every argument named `prefix.*`, like `db.host` and `db.port` for `"db"`, with the prefix stripped.
+    /// Arguments provided without a value (like flags) have the value `"true"`.
     /// 
     /// # Example
     /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
     /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("db.host"), Arg::new().input("db.port"), Arg::new().input("log")]);
+    /// 
+    /// let output = parser.try_get_matches_from(["--db.host", "localhost", "--db.port", "5432"]).unwrap();
+    /// let db = output.namespace("db");
+    /// assert_eq!(db["host"], "localhost");
+    /// assert_eq!(db["port"], "5432");
     /// ```
-    pub fn new() -> Parser {
-        Parser { args: RefCell::new(vec![]) }
+    pub fn namespace(&self, prefix: &str) -> HashMap<String, String> {
+        self.values.iter()
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix(prefix)?.strip_prefix('.')?;
+                Some((String::from(key), value.clone().unwrap_or_else(|| String::from("true"))))
+            })
+            .collect()
     }
 
-    /// Adds an argument to the parser.
+    /// Returns the number of times an [`Arg::count()`] flag was provided, or `0` if it wasn't.
+    pub fn count(&self, name: &str) -> u32 {
+        self.counts.get(name).copied().unwrap_or(0)
+    }
+
+    /// Returns the warnings found by [`Parser::parse_with_warnings()`].
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    /// Returns counts of what happened while parsing, like how many options were called.
     /// 
     /// # Example
     /// ```
-    /// let arg = Arg::new().param("num");
+    /// # use simple_cli_parser::{Arg, Parser};
     /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("verbose").short('v'), Arg::new().input("out"), Arg::new().param("file")]);
     /// 
-    /// parser.add_arg(arg);
+    /// let stats = parser.try_get_matches_from(["-v", "--out", "a.txt", "b.txt"]).unwrap().stats();
+    /// assert_eq!((stats.tokens, stats.options, stats.positionals), (4, 2, 1));
     /// ```
-    pub fn add_arg(&self, arg: Arg) {
-        self.args.borrow_mut().push(arg);
+    /// Output from [`Parser::parse_map()`] has no tokens, so all of its counts are `0`.
+    pub fn stats(&self) -> ParseStats {
+        self.stats
     }
 
-    /// Adds a vector of arguments to the parser.
+    /// Returns how the option was spelled when it was called, e.g. to echo the user's spelling in a message.
     /// 
     /// # Example
     /// ```
-    /// let mut args = vec![
-    ///     Arg::new().param("num1"),
-    ///     Arg::new().param("num2"),
-    /// ];
+    /// # use simple_cli_parser::{Arg, MatchedForm, Parser};
     /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("help").short('h'), Arg::new().input("out").alias("output")]);
     /// 
-    /// parser.add_args(args);
+    /// let output = parser.try_get_matches_from(["-h", "--output", "a.txt"]).unwrap();
+    /// assert_eq!(output.matched_form("help"), Some(MatchedForm::Short('h')));
+    /// assert_eq!(output.matched_form("out"), Some(MatchedForm::Alias(String::from("output"))));
     /// ```
-    pub fn add_args(&self, mut args: Vec<Arg>) {
-        self.args.borrow_mut().append(&mut args);
+    /// Returns `None` for params and for options which weren't called. If an option was called several times, this is its last form.
+    pub fn matched_form(&self, name: &str) -> Option<MatchedForm> {
+        self.forms.get(name).cloned()
     }
 
-    /// Returns the arguments associated with this parser as a vector.
-    /// 
-    /// # Example
-    /// ```
-    /// let args = parser.args();
-    /// ```
-    pub fn args(&self) -> Vec<Arg> {
-        self.args.borrow().clone()
+    /// Returns the program name recorded by [`Parser::parse_with_program_name()`].
+    pub fn bin_name(&self) -> Option<&str> {
+        self.bin_name.as_deref()
     }
 
-    /// Returns the number of arguments associated with this parser.
-    /// 
-    /// # Example
-    /// ```
-    /// let n = parser.len();
-    /// ```
+    /// Returns the number of arguments provided.
     pub fn len(&self) -> usize {
-        self.args.borrow().len()
+        self.names().len()
     }
 
-    fn get_err(&self, reason: InvalidCommandReasons) -> Result<HashMap<String, Option<String>>, Box<dyn Error>> {
-        return Err(Box::new(InvalidCommandError::new(reason)))
+    /// Returns `true` if no arguments were provided.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty() && self.lists.is_empty() && self.defines.is_empty()
     }
 
-    /// Parses through the remaining arguments and returns a hashmap of arguments passed and their relevant values.
+    /// Serializes the output into a JSON object with keys sorted by name, in the shape of its [`serde::Serialize`] impl.
+    /// Arguments without a value (like flags) are `true`, arguments with multiple values are arrays,
+    /// [`Arg::defines()`] arguments are objects, and all other values are strings.
     /// 
     /// # Example
     /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
     /// let parser = Parser::new();
-    /// let mut args = vec![
-    ///     Arg::new().param("p1"),
-    ///     Arg::new().param("p2"),
-    ///     Arg::new().flag("help").short('h');
-    /// ];
-    /// parser.add_args(args);
+    /// parser.add_args(vec![Arg::new().flag("verbose"), Arg::new().input("out")]);
     /// 
-    /// let mut input_args = std::env::args();
-    /// input_args.next();
-    /// 
-    /// let hashmap = parser.parse(input_args).unwrap();
-    /// println!("p1: {}, p2: {}", hashmap.get("p1"), hashmap.get("p2"));
-    /// if hashmap.contains_key("help") {
-    ///     println!("Help requested!");
-    /// }
+    /// let output = parser.try_get_matches_from(["--verbose", "--out", "a.txt"]).unwrap();
+    /// assert_eq!(output.to_json(), r#"{"out":"a.txt","verbose":true}"#);
     /// ```
-    pub fn parse(&self, args: &mut impl Iterator<Item = String>) -> Result<HashMap<String, Option<String>>, Box<dyn Error>> {
-        let mut hashmap: HashMap<String, Option<String>> = HashMap::new();
-        let mut prev_arg: Option<Box<Arg>> = None;
-        let mut args = args.peekable();
-        let mut parser_args = self.args.clone().take();
-
-        while let Some(c_arg) = args.next() {
-            if c_arg.starts_with("-") {
-                // Return error if calling a new argument without providing a follow up argument to the previous one
-                if prev_arg.is_some() {
-                    return self.get_err(InvalidCommandReasons::Unexpected(c_arg));
-                }
+    /// Only available with the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("a map with string keys always serializes")
+    }
 
-                if c_arg.starts_with("--") {
-                    // Full arg
-                    let mut found = false;
-                    for arg in &parser_args {
-                        if c_arg.ends_with(&arg.name) && c_arg.len() == arg.name.len() + 2 {
-                            found = true;
-                            if arg.expecting {
-                                prev_arg = Some(Box::new(arg.clone()));
-                            } else {
-                                match hashmap.insert(arg.name.clone(), None) {
-                                    Some(_) => return self.get_err(InvalidCommandReasons::Duplicate(c_arg)),
-                                    None => {},
-                                };
-                                prev_arg = None;
-                            }
-                        }
-                    }
-                    if !found {
-                        return self.get_err(InvalidCommandReasons::Unexpected(c_arg));
-                    }
-                } else {
-                    // Short arg
-                    let mut found = false;
-                    for arg in &parser_args {
-                        if let ArgTypes::Short(c) = arg.arg_type {
-                            if c_arg.ends_with(c) && c_arg.len() == 2 {
-                                found = true;
-                                if arg.expecting {
-                                    prev_arg = Some(Box::new(arg.clone()));
-                                } else {
-                                    match hashmap.insert(arg.name.clone(), None) {
-                                        Some(_) => return self.get_err(InvalidCommandReasons::Duplicate(c_arg)),
-                                        None => {},
-                                    };
-                                    prev_arg = None;
-                                }
-                            }
-                        }
-                    }
-                    if !found {
-                        return self.get_err(InvalidCommandReasons::Unexpected(c_arg));
-                    }
-                }
-            } else {
-                // non-argument token
-                if prev_arg.is_none() {
-                    // params
-                    let mut found = false;
-                    for arg in &mut parser_args {
-                        if let ArgTypes::Param(used) = arg.arg_type {
-                            if used {
-                                continue;
-                            }
-                            
-                            match hashmap.insert(arg.name.clone(), Some(c_arg.clone())) {
-                                Some (_) => return self.get_err(InvalidCommandReasons::Duplicate(c_arg)),
-                                None => {}
-                            };
-                            arg.set_used(true);
-                            prev_arg = None;
-                            found = true;
-                            break;
-                        }
-                    }
+    /// Returns the keys of the arguments provided, sorted and without repeats.
+    /// An argument with several values is marked in `values` and stored in `lists`, but is still one argument.
+    fn names(&self) -> Vec<&String> {
+        let mut names: Vec<&String> = self.values.keys().chain(self.lists.keys()).chain(self.defines.keys()).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
 
-                    if !found {
-                        return self.get_err(InvalidCommandReasons::Unexpected(c_arg));
-                    }
-                } else {
-                    match hashmap.insert(prev_arg.unwrap().name, Some(c_arg.clone())) {
-                        Some (_) => return self.get_err(InvalidCommandReasons::Duplicate(c_arg)),
-                        None => {}
-                    }
-                    prev_arg = None;
-                }
-            };
+    /// Converts the output into the hashmap returned by [`Parser::parse()`].
+    pub fn into_map(self) -> HashMap<String, Option<String>> {
+        self.values
+    }
+}
 
-            if args.peek().is_none() && prev_arg.is_some() {
-                return self.get_err(InvalidCommandReasons::Missing);
-            }
-        }
+/// Serializes the output as a map with keys sorted by name, like [`ParseOutput::to_json()`].
+#[cfg(feature = "serde")]
+impl serde::Serialize for ParseOutput {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
 
-        for arg in parser_args {
-            if let ArgTypes::Param(false) = arg.arg_type {
-                return self.get_err(InvalidCommandReasons::Missing);
+        let names = self.names();
+        let mut map = serializer.serialize_map(Some(names.len()))?;
+        for name in names {
+            match (self.values.get(name), self.lists.get(name), self.defines.get(name)) {
+                (_, Some(list), _) => map.serialize_entry(name, list)?,
+                (_, _, Some(defines)) => {
+                    let sorted: std::collections::BTreeMap<&String, &String> = defines.iter().collect();
+                    map.serialize_entry(name, &sorted)?;
+                },
+                (Some(Some(value)), _, _) => map.serialize_entry(name, value)?,
+                _ => map.serialize_entry(name, &true)?,
             }
         }
+        map.end()
+    }
+}
 
-        Ok(hashmap)
+/// Quotes `s` for a POSIX shell if it contains anything but plain chars, so [`Parser::parse_str()`] reads it back as one token.
+fn shell_escape(s: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+    if !s.is_empty() && s != "--" && s.chars().all(plain) {
+        String::from(s)
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
     }
 }
 
+/// Splits a short option like `-o` or `-o=value` into its char and attached value.
+fn split_short(token: &str) -> Option<(char, Option<&str>)> {
+    let rest = token.strip_prefix('-').filter(|rest| !rest.starts_with('-'))?;
+    let mut chars = rest.chars();
+    let short = chars.next()?;
+    let after = chars.as_str();
 
+    match after.strip_prefix('=') {
+        Some(value) => Some((short, Some(value))),
+        None if after.is_empty() => Some((short, None)),
+        None => None,
+    }
+}
 
-// Tests
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Returns the value attached to an option with `=`, like `file` in `--out=file` or `-o=file`.
+fn attached_value(token: &str) -> Option<&str> {
+    match token.strip_prefix("--") {
+        Some(rest) => rest.split_once('=').map(|(_, value)| value),
+        None => split_short(token)?.1,
+    }
+}
+
+/// Finds the argument called by an option with its own [`Arg::prefix()`], like `-Xlint`, along with any value attached with `=`.
+fn find_prefixed<'a, 't>(parser_args: &'a [Arg], token: &'t str) -> Option<(&'a Arg, Option<&'t str>)> {
+    parser_args.iter().find_map(|arg| {
+        let rest = token.strip_prefix(arg.prefix.as_deref()?)?;
+        let (name, value) = match rest.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (rest, None),
+        };
+        (arg.name == name || arg.aliases.iter().any(|a| a == name)).then_some((arg, value))
+    })
+}
+
+/// Returns how `token` spells the option `arg`, which it is known to call.
+fn matched_form(arg: &Arg, token: &str) -> MatchedForm {
+    let long = arg.prefix.as_deref()
+        .and_then(|prefix| token.strip_prefix(prefix))
+        .or_else(|| token.strip_prefix("--"));
+    let rest = long.or_else(|| token.strip_prefix(['+', '-'])).unwrap_or(token);
+    let name = rest.split('=').next().unwrap_or(rest);
+
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if long.is_none() && arg.short == Some(c) => MatchedForm::Short(c),
+        _ if name == arg.name => MatchedForm::Long,
+        _ => MatchedForm::Alias(String::from(name)),
+    }
+}
+
+/// Pushes the value given for `key` onto `order` if the order is being kept, only building the value then.
+fn record(order: &mut Option<&mut Vec<(String, Value)>>, key: &str, value: impl FnOnce() -> Value) {
+    if let Some(order) = order {
+        order.push((String::from(key), value()));
+    }
+}
+
+/// Splits a cluster of shorts like `-xvf` into the arguments it calls.
+/// Once an argument expecting a value is reached, the rest of the cluster is its value, with an optional leading `=`,
+/// so `-xvfout` and `-xvf=out` both give `f` the value `out`. An expecting argument at the end of the cluster has no value.
+fn split_cluster<'a, 't>(parser_args: &'a [Arg], token: &'t str) -> Option<Vec<(&'a Arg, Option<&'t str>)>> {
+    let rest = token.strip_prefix('-').filter(|rest| !rest.is_empty() && !rest.starts_with('-'))?;
+    let mut cluster = vec![];
+
+    for (i, c) in rest.char_indices() {
+        let arg = parser_args.iter().find(|arg| arg.short == Some(c))?;
+        if arg.expecting {
+            let after = &rest[i + c.len_utf8()..];
+            let value = after.strip_prefix('=').unwrap_or(after);
+            cluster.push((arg, Some(value).filter(|_| !after.is_empty())));
+            break;
+        }
+        cluster.push((arg, None));
+    }
+
+    Some(cluster)
+}
+
+/// Greedily wraps `text` into lines of at most `width` chars, breaking only at whitespace.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    lines
+}
+
+/// Splits a command line into tokens on whitespace, honoring `'` and `"` quotes and `\\` escapes.
+/// Each token is paired with whether any part of it was quoted.
+fn tokenize(line: &str) -> Result<Vec<(String, bool)>, ParseError> {
+    let mut tokens = vec![];
+    let mut chars = line.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Ok(tokens);
+        }
+
+        let mut token = String::new();
+        let mut quoted = false;
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+            match c {
+                '"' | '\'' => {
+                    quoted = true;
+                    loop {
+                        match chars.next() {
+                            Some(q) if q == c => break,
+                            Some('\\') if c == '"' => token.extend(chars.next()),
+                            Some(ch) => token.push(ch),
+                            None => return Err(ParseError::Unexpected(format!("{}{}", c, token))),
+                        }
+                    }
+                },
+                '\\' => token.extend(chars.next()),
+                c => token.push(c),
+            }
+        }
+        tokens.push((token, quoted));
+    }
+}
+
+fn is_negative_number(s: &str) -> bool {
+    let Some(num) = s.strip_prefix('-') else {
+        return false;
+    };
+    let (whole, fraction) = match num.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (num, None),
+    };
+
+    !whole.is_empty()
+        && whole.chars().all(|c| c.is_ascii_digit())
+        && fraction.is_none_or(|f| !f.is_empty() && f.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn split_number(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+fn parse_byte_size(s: &str) -> Option<u64> {
+    let (num, suffix) = split_number(s.trim());
+    let num: u64 = num.parse().ok()?;
+    let multiplier: u64 = match suffix.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        _ => return None,
+    };
+    num.checked_mul(multiplier)
+}
+
+fn parse_duration(s: &str) -> Option<Duration> {
+    let (num, suffix) = split_number(s.trim());
+    let num: u64 = num.parse().ok()?;
+    let millis: u64 = match suffix.trim() {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        _ => return None,
+    };
+    num.checked_mul(millis).map(Duration::from_millis)
+}
+
+#[derive(Clone, Debug)]
+enum ValueKind {
+    Text,
+    ByteSize,
+    Duration,
+}
+
+#[derive(Clone)]
+struct ValueMap(Rc<dyn Fn(String) -> String>);
+
+impl std::fmt::Debug for ValueMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "ValueMap")
+    }
+}
+
+#[derive(Clone)]
+struct DefaultFn(Rc<dyn Fn() -> String>);
+
+impl std::fmt::Debug for DefaultFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "DefaultFn")
+    }
+}
+
+#[derive(Clone)]
+struct ErrorFormatter(Rc<dyn Fn(&ParseError) -> String>);
+
+type EnvLookup = dyn Fn(&str) -> Option<String>;
+
+#[derive(Clone)]
+struct EnvSource(Rc<EnvLookup>);
+
+type TokenCallback = dyn FnMut(&str, Classification);
+
+#[derive(Clone)]
+struct TokenObserver(Rc<RefCell<TokenCallback>>);
+
+#[derive(Clone, Debug)]
+enum ArgTypes {
+    Param(bool),
+    Input,
+    Flag,
+    None
+}
+
+/// The kind of an [`Arg`], as used by [`ArgSpec`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// A positional argument, see [`Arg::param()`].
+    Param,
+    /// An option expecting a value, see [`Arg::input()`].
+    Input,
+    /// An option without a value, see [`Arg::flag()`].
+    Flag,
+}
+
+/// The type of a value in the schema of an [`Arg::defines()`] argument, set with [`Arg::define_schema()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefineKind {
+    /// Any string.
+    Text,
+    /// A whole number like `8080` or `-1`.
+    Int,
+    /// `true` or `false`.
+    Bool,
+}
+
+/// A value found by [`Parser::parse_ordered()`], in the shape the argument gave it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// A flag, which has no value.
+    Flag,
+    /// A single value, like that of an input or param.
+    Str(String),
+    /// Several values given at once, like those of [`Arg::value_delimiter()`] or [`Arg::takes_positionals()`].
+    Multi(Vec<String>),
+}
+
+/// A plain description of an [`Arg`], for building one with [`Arg::build()`] instead of chaining methods.
+/// 
+/// # Example
+/// ```
+/// # use simple_cli_parser::{Arg, ArgKind, ArgSpec};
+/// let arg = Arg::build(ArgSpec { name: String::from("output"), kind: ArgKind::Input, short: Some('o'), required: true });
+/// assert_eq!(arg.get_name(), "output");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgSpec {
+    /// The long name of the argument.
+    pub name: String,
+    /// Whether the argument is a param, input or flag.
+    pub kind: ArgKind,
+    /// The short char of the argument, if any.
+    pub short: Option<char>,
+    /// Whether the argument must be provided. Params are always required.
+    pub required: bool,
+}
+
+/// Represents a single argument which can be passed to a [`Parser`].
+/// 
+/// # Example
+/// ```no_run
+/// # use simple_cli_parser::{Arg, Parser};
+/// let arg = Arg::new().param("num");
+/// let parser = Parser::new();
+/// let mut args = std::env::args();
+/// args.next();
+/// 
+/// parser.add_arg(arg);
+/// let output = parser.parse(&mut args).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Arg {
+    name: String,
+    arg_type: ArgTypes,
+    short: Option<char>,
+    aliases: Vec<String>,
+    expecting: bool,
+    raw_value: bool,
+    value_kind: ValueKind,
+    map: Option<ValueMap>,
+    default: Option<DefaultFn>,
+    default_value: Option<String>,
+    id: Option<String>,
+    implies: Vec<String>,
+    countable: bool,
+    defines: bool,
+    required: bool,
+    terminator: Option<String>,
+    group: Option<String>,
+    auto_short: bool,
+    non_empty: bool,
+    help: Option<String>,
+    positionals: usize,
+    max_positionals: usize,
+    requires_any: Vec<String>,
+    required_unless: Vec<String>,
+    define_schema: Vec<(String, DefineKind)>,
+    deny_unknown_keys: bool,
+    disables_validation: bool,
+    delimiter: Option<char>,
+    keep_empty: bool,
+    env_flag: Option<String>,
+    hide_default: bool,
+    prefix: Option<String>,
+    long_help: Option<String>,
+    allowed: Vec<String>,
+}
+
+impl Arg {
+    /// Create a new arg object, note you must call further methods on this for it to be useful.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new();
+    /// ```
+    #[must_use]
+    pub fn new() -> Arg {
+        
+        Arg { name: String::new(), arg_type: ArgTypes::None, short: None, aliases: vec![], expecting: false, raw_value: false, value_kind: ValueKind::Text, map: None, default: None, default_value: None, id: None, implies: vec![], countable: false, defines: false, required: false, terminator: None, group: None, auto_short: false, non_empty: false, help: None, positionals: 0, max_positionals: 0, requires_any: vec![], required_unless: vec![], define_schema: vec![], deny_unknown_keys: false, disables_validation: false, delimiter: None, keep_empty: false, env_flag: None, hide_default: false, prefix: None, long_help: None, allowed: vec![] }
+    }
+
+    /// Creates an argument from an [`ArgSpec`], which is useful for generated code.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, ArgKind, ArgSpec, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::build(ArgSpec { name: String::from("verbose"), kind: ArgKind::Flag, short: Some('v'), required: false }));
+    /// 
+    /// let output = parser.try_get_matches_from(["-v"]).unwrap();
+    /// assert!(output.is_present("verbose"));
+    /// ```
+    #[must_use]
+    pub fn build(spec: ArgSpec) -> Arg {
+        let arg = match spec.kind {
+            ArgKind::Param => Arg::new().param(&spec.name),
+            ArgKind::Input => Arg::new().input(&spec.name),
+            ArgKind::Flag => Arg::new().flag(&spec.name),
+        };
+        Arg { short: spec.short, ..arg.required(spec.required) }
+    }
+
+    /// A parameter argument, or one that does not expect any argument to come before it.
+    /// Note that the order that these are added to the parser matters.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().param("p1");
+    /// ```
+    /// `arg` is a required argument and the [`Parser::parse()`] will return an error if it is not present.
+    #[must_use]
+    pub fn param(self, name: &str) -> Arg {
+        self.param_kind().long(name)
+    }
+
+    /// An optional argument that expects a value to follow directly after it.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().input("inp");
+    /// ```
+    /// Upon parsing, if `--inp` is one of the arguments called, `arg` will be in the output with whatever string comes next in the arguments.
+    /// The value can also be attached as `--inp=value`.
+    /// If the next token is a bare `--`, parsing fails with [`ParseError::MissingValue`] rather than guessing which the `--` was meant as.
+    /// Use [`Arg::take_next_raw()`] to take `--` as the value instead.
+    #[must_use]
+    pub fn input(self, name: &str) -> Arg {
+        self.input_kind().long(name)
+    }
+
+    /// A flag argument, or one that toggles a setting without expecting another token afterwards.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().flag("optional");
+    /// ```
+    /// Upon parsing, if `--optional` is one of the arguments called, `arg` will be in the output with the value `true`.
+    #[must_use]
+    pub fn flag(self, name: &str) -> Arg {
+        self.flag_kind().long(name)
+    }
+
+    /// Makes the argument a parameter without changing its name.
+    /// See [`Arg::param()`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().param_kind().long("p1");
+    /// ```
+    #[must_use]
+    pub fn param_kind(self) -> Arg {
+        Arg { arg_type: ArgTypes::Param(false), expecting: false, ..self }
+    }
+
+    /// Makes the argument an input without changing its name.
+    /// See [`Arg::input()`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().input_kind().long("output").short('o');
+    /// ```
+    #[must_use]
+    pub fn input_kind(self) -> Arg {
+        Arg { arg_type: ArgTypes::Input, expecting: true, ..self }
+    }
+
+    /// Makes the argument a flag without changing its name.
+    /// See [`Arg::flag()`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().flag_kind().long("verbose");
+    /// ```
+    #[must_use]
+    pub fn flag_kind(self) -> Arg {
+        Arg { arg_type: ArgTypes::Flag, expecting: false, ..self }
+    }
+
+    /// Sets the long name of the argument without changing its kind.
+    /// This is the name used to call the argument with `--` and the key it is stored under after parsing.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().short('o').input_kind().long("output");
+    /// ```
+    /// The `arg` variable can be called by `--output` or by `-o`.
+    #[must_use]
+    pub fn long(self, name: &str) -> Arg {
+        Arg { name: String::from(name), ..self }
+    }
+
+    /// Sets the key the argument is stored under after parsing, so it can differ from the long name it is called by.
+    /// Without an id, the argument is stored under its long name.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("farbe").id("color"));
+    /// 
+    /// let output = parser.try_get_matches_from(["--farbe", "rot"]).unwrap();
+    /// assert_eq!(output.value_of("color"), Some("rot"));
+    /// assert!(!output.is_present("farbe"));
+    /// ```
+    /// Other arguments refer to it by its id as well, e.g. in [`Arg::implies()`] and [`Parser::with_defaults_from()`].
+    #[must_use]
+    pub fn id(self, id: &str) -> Arg {
+        Arg { id: Some(String::from(id)), ..self }
+    }
+
+    /// Sets a short option for the argument, allowing it to be called with a char rather than a string.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().flag("help").short('h');
+    /// ```
+    /// The `arg` variable can be called by `--help` or by `-h`.
+    /// Shorts can also be grouped, like `-xvf out`, `-xvfout` or `-xvf=out`, where only the last one may take a value.
+    #[must_use]
+    pub fn short(self, ch: char) -> Arg {
+        Arg { short: Some(ch), ..self }
+    }
+
+    /// Uses the first letter of the long name as the short option, if no other argument in the parser already uses it.
+    /// The short is picked when the argument is added to a [`Parser`], and is skipped if the letter is taken.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("verbose").auto_short(), Arg::new().flag("version").auto_short()]);
+    /// 
+    /// assert!(parser.try_get_matches_from(["-v"]).unwrap().is_present("verbose"));
+    /// ```
+    /// An explicit [`Arg::short()`] always takes precedence.
+    #[must_use]
+    pub fn auto_short(self) -> Arg {
+        Arg { auto_short: true, ..self }
+    }
+
+    /// Adds another long name the argument can be called by.
+    /// The argument is still stored under its main name after parsing.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().flag("color").alias("colour");
+    /// ```
+    /// The `arg` variable can be called by `--color` or by `--colour`.
+    #[must_use]
+    pub fn alias(mut self, name: &str) -> Arg {
+        self.aliases.push(String::from(name));
+        self
+    }
+
+    /// Makes an input take the token directly after it as its value, no matter what it looks like.
+    /// This includes tokens that look like options or are even registered options, so use it with care.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("exclude").take_next_raw(), Arg::new().flag("verbose")]);
+    /// 
+    /// let output = parser.try_get_matches_from(["--exclude", "--verbose"]).unwrap();
+    /// assert_eq!(output.value_of("exclude"), Some("--verbose"));
+    /// assert!(!output.is_present("verbose"));
+    /// ```
+    /// The token after the input is always its value, even if it is `--`.
+    /// If the input is the last token, parsing still fails with [`ParseError::MissingValue`].
+    #[must_use]
+    pub fn take_next_raw(self) -> Arg {
+        Arg { raw_value: true, ..self }
+    }
+
+    /// Interprets the value as a size in bytes, like `512`, `10MB` or `4KiB`.
+    /// Decimal suffixes (`KB`, `MB`, `GB`, `TB`) are powers of 1000 and binary suffixes (`KiB`, `MiB`, `GiB`, `TiB`) are powers of 1024.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("max-size").byte_size());
+    /// 
+    /// let output = parser.try_get_matches_from(["--max-size", "10MB"]).unwrap();
+    /// assert_eq!(output.byte_size_of("max-size"), Some(10_000_000));
+    /// ```
+    /// The value is stored as the number of bytes, and an invalid size returns [`ParseError::Value`].
+    #[must_use]
+    pub fn byte_size(self) -> Arg {
+        Arg { value_kind: ValueKind::ByteSize, ..self }
+    }
+
+    /// Interprets the value as a duration, like `250ms`, `30s`, `5m` or `2h`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// # use std::time::Duration;
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("timeout").duration());
+    /// 
+    /// let output = parser.try_get_matches_from(["--timeout", "30s"]).unwrap();
+    /// assert_eq!(output.duration_of("timeout"), Some(Duration::from_secs(30)));
+    /// ```
+    /// The value is stored as a number of milliseconds, and an invalid duration returns [`ParseError::Value`].
+    #[must_use]
+    pub fn duration(self) -> Arg {
+        Arg { value_kind: ValueKind::Duration, ..self }
+    }
+
+    /// Rejects an empty value, like the one given by `-n=` or `--name ''`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, ParseError, Parser, ValueErrorKind};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("name").short('n').non_empty());
+    /// 
+    /// let res = parser.try_get_matches_from(["-n="]);
+    /// assert!(matches!(res, Err(ParseError::Value { kind: ValueErrorKind::Empty, .. })));
+    /// ```
+    /// The check runs after [`Arg::map_value()`], so a value which is mapped to an empty string is rejected as well.
+    #[must_use]
+    pub fn non_empty(self) -> Arg {
+        Arg { non_empty: true, ..self }
+    }
+
+    /// Makes an input which only takes one of `values`, and is `default` when it isn't given, like `--sort asc|desc`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, ParseError, Parser, ValueErrorKind};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().long("sort").toggle(&["asc", "desc"], "asc"));
+    /// 
+    /// assert_eq!(parser.try_get_matches_from(["--sort", "desc"]).unwrap().value_of("sort"), Some("desc"));
+    /// assert_eq!(parser.try_get_matches_from(Vec::<String>::new()).unwrap().value_of("sort"), Some("asc"));
+    /// 
+    /// let res = parser.try_get_matches_from(["--sort", "up"]);
+    /// assert!(matches!(res, Err(ParseError::Value { kind: ValueErrorKind::NotAllowed(_), .. })));
+    /// ```
+    /// The check runs after [`Arg::map_value()`], so e.g. lowercasing the value lets `DESC` through.
+    #[must_use]
+    pub fn toggle(self, values: &[&str], default: &str) -> Arg {
+        Arg {
+            allowed: values.iter().map(|value| String::from(*value)).collect(),
+            ..self.input_kind().default_value(default)
+        }
+    }
+
+    /// Transforms the value of the argument before it is stored, e.g. to trim or lowercase it.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("level").map_value(|v| v.to_lowercase()));
+    /// 
+    /// let output = parser.try_get_matches_from(["--level", "DEBUG"]).unwrap();
+    /// assert_eq!(output.value_of("level"), Some("debug"));
+    /// ```
+    /// The transformation runs before the value is interpreted by [`Arg::byte_size()`] or [`Arg::duration()`],
+    /// so the value goes through capture, then mapping, then validation, before finally being stored.
+    #[must_use]
+    pub fn map_value(self, f: impl Fn(String) -> String + 'static) -> Arg {
+        Arg { map: Some(ValueMap(Rc::new(f))), ..self }
+    }
+
+    /// Sets a default value for an input, computed by `f` only if the input isn't provided.
+    /// This is useful for defaults that are expensive or depend on the environment.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("jobs").default_with(|| String::from("4")));
+    /// 
+    /// let output = parser.try_get_matches_from(Vec::<String>::new()).unwrap();
+    /// assert_eq!(output.value_of("jobs"), Some("4"));
+    /// ```
+    /// A value provided on the command line always takes precedence over the default.
+    /// The default goes through the same processing as a provided value, like [`Arg::map_value()`].
+    /// Since `f` only runs while parsing, [`Parser::usage()`] doesn't show this default.
+    #[must_use]
+    pub fn default_with(self, f: impl Fn() -> String + 'static) -> Arg {
+        Arg { default: Some(DefaultFn(Rc::new(f))), ..self }
+    }
+
+    /// Sets a fixed default value for an input, used if the input isn't provided and shown by [`Parser::usage()`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("jobs").default_value("4"));
+    /// 
+    /// let output = parser.try_get_matches_from(Vec::<String>::new()).unwrap();
+    /// assert_eq!(output.value_of("jobs"), Some("4"));
+    /// assert_eq!(parser.usage(), "Options:\n      --jobs <jobs>  [default: 4]\n");
+    /// ```
+    /// A default from [`Arg::default_with()`] takes precedence over this one, and then neither is shown.
+    #[must_use]
+    pub fn default_value(self, value: &str) -> Arg {
+        Arg { default_value: Some(String::from(value)), ..self }
+    }
+
+    /// Makes a flag countable, so it can be repeated and the number of times it was provided is recorded.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("verbose").short('v').count());
+    /// 
+    /// let output = parser.try_get_matches_from(["-v", "-v", "--verbose"]).unwrap();
+    /// assert_eq!(output.count("verbose"), 3);
+    /// ```
+    /// Repeating a countable flag is never a duplicate, regardless of [`Parser::on_duplicate()`].
+    #[must_use]
+    pub fn count(self) -> Arg {
+        Arg { countable: true, ..self }
+    }
+
+    /// Makes an input collect repeated `key=value` pairs, like a compiler's `-D name=value`.
+    /// Each value is split on its first `=`, and a value without one is stored with the value `"true"`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("define").short('D').defines());
+    /// 
+    /// let output = parser.try_get_matches_from(["-D", "a=1", "-D", "b=2", "-D", "debug"]).unwrap();
+    /// let defines = output.defines_of("define").unwrap();
+    /// assert_eq!(defines["a"], "1");
+    /// assert_eq!(defines["debug"], "true");
+    /// ```
+    /// The pairs are available through [`ParseOutput::defines_of()`].
+    /// Repeating a key keeps the last value, and repeating the argument is never a duplicate.
+    #[must_use]
+    pub fn defines(self) -> Arg {
+        Arg { defines: true, ..self.input_kind() }
+    }
+
+    /// Gives the keys of an [`Arg::defines()`] argument types, so their values are checked and normalized, like `PORT=8080`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, DefineKind, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("env").short('e').define_schema(&[("PORT", DefineKind::Int), ("DEBUG", DefineKind::Bool)]));
+    /// 
+    /// let output = parser.try_get_matches_from(["-e", "PORT=+8080", "-e", "DEBUG", "-e", "USER=me"]).unwrap();
+    /// let env = output.defines_of("env").unwrap();
+    /// assert_eq!(env["PORT"].parse::<i64>(), Ok(8080));
+    /// assert_eq!(env["DEBUG"], "true");
+    /// assert_eq!(env["USER"], "me");
+    /// assert!(parser.try_get_matches_from(["-e", "PORT=http"]).is_err());
+    /// ```
+    /// A value of the wrong type returns [`ParseError::Value`]. Keys not in the schema are stored as text,
+    /// unless [`Arg::deny_unknown_keys()`] is set.
+    #[must_use]
+    pub fn define_schema(mut self, schema: &[(&str, DefineKind)]) -> Arg {
+        self.define_schema.extend(schema.iter().map(|(key, kind)| (String::from(*key), *kind)));
+        self.defines()
+    }
+
+    /// Rejects keys which aren't in the [`Arg::define_schema()`] of an [`Arg::defines()`] argument.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, DefineKind, ParseError, Parser, ValueErrorKind};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("env").define_schema(&[("PORT", DefineKind::Int)]).deny_unknown_keys());
+    /// 
+    /// let res = parser.try_get_matches_from(["--env", "USER=me"]);
+    /// assert_eq!(res, Err(ParseError::Value {
+    ///     name: String::from("env"),
+    ///     value: String::from("USER=me"),
+    ///     kind: ValueErrorKind::UnknownKey,
+    /// }));
+    /// ```
+    #[must_use]
+    pub fn deny_unknown_keys(self) -> Arg {
+        Arg { deny_unknown_keys: true, ..self.defines() }
+    }
+
+    /// Makes an input or flag required, so parsing fails with [`ParseError::MissingRequired`] if it isn't provided.
+    /// Params are always required, see [`Parser::require_params()`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, ParseError, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("config").required(true), Arg::new().flag("verbose")]);
+    /// 
+    /// let res = parser.try_get_matches_from(["--verbose"]);
+    /// assert_eq!(res, Err(ParseError::MissingRequired(vec![String::from("config")])));
+    /// ```
+    /// A value from [`Parser::with_defaults_from()`] or [`Arg::default_with()`] also counts as provided.
+    #[must_use]
+    pub fn required(self, required: bool) -> Arg {
+        Arg { required: required && !matches!(self.arg_type, ArgTypes::Param(_)), ..self }
+    }
+
+    /// Makes an input or flag required unless `other` is provided, like `--config` unless `--no-config` is given.
+    /// Calling this again adds more arguments which each lift the requirement.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, ParseError, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("config").required_unless("no-config"), Arg::new().flag("no-config")]);
+    /// 
+    /// assert!(parser.try_get_matches_from(["--no-config"]).is_ok());
+    /// let res = parser.try_get_matches_from(Vec::<String>::new());
+    /// assert_eq!(res, Err(ParseError::MissingRequired(vec![String::from("config")])));
+    /// ```
+    #[must_use]
+    pub fn required_unless(mut self, other: &str) -> Arg {
+        self.required_unless.push(String::from(other));
+        self.required(true)
+    }
+
+    /// Makes the argument skip the checks for missing params, [`Arg::required()`] and [`Arg::requires_any()`] when provided, like a `--force`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().param("target"), Arg::new().flag("force").disables_validation()]);
+    /// 
+    /// let output = parser.try_get_matches_from(["--force"]).unwrap();
+    /// assert!(!output.is_present("target"));
+    /// ```
+    /// Malformed input like unknown tokens, too many positionals or invalid values still returns an error.
+    #[must_use]
+    pub fn disables_validation(self) -> Arg {
+        Arg { disables_validation: true, ..self }
+    }
+
+    /// Splits the value of an input on `delimiter`, like `--features a,b,c`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("features").value_delimiter(','));
+    /// 
+    /// let output = parser.try_get_matches_from(["--features", "a,,b,c,"]).unwrap();
+    /// assert_eq!(output.values_of("features").unwrap(), ["a", "b", "c"]);
+    /// ```
+    /// The values are available through [`ParseOutput::values_of()`]. Empty parts are skipped unless [`Arg::keep_empty_values()`] is set,
+    /// and a single trailing delimiter is ignored.
+    #[must_use]
+    pub fn value_delimiter(self, delimiter: char) -> Arg {
+        Arg { delimiter: Some(delimiter), ..self.input_kind() }
+    }
+
+    /// Keeps the empty parts of a value split by [`Arg::value_delimiter()`], so `a,,b` gives `a`, an empty value and `b`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("columns").value_delimiter(',').keep_empty_values());
+    /// 
+    /// let output = parser.try_get_matches_from(["--columns", "a,,b,"]).unwrap();
+    /// assert_eq!(output.values_of("columns").unwrap(), ["a", "", "b"]);
+    /// ```
+    #[must_use]
+    pub fn keep_empty_values(self) -> Arg {
+        Arg { keep_empty: true, ..self }
+    }
+
+    /// Makes an input take every following token as a value until a bare `--` or the end of the input, like `xargs`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("files").values_until_terminator(), Arg::new().flag("verbose")]);
+    /// 
+    /// let output = parser.try_get_matches_from(["--files", "a", "--verbose", "c", "--", "--verbose"]).unwrap();
+    /// assert_eq!(output.values_of("files").unwrap(), ["a", "--verbose", "c"]);
+    /// assert!(output.is_present("verbose"));
+    /// ```
+    /// The values are available through [`ParseOutput::values_of()`], and tokens that look like options are values too.
+    /// The `--` only ends the values, so the tokens after it are parsed as usual.
+    #[must_use]
+    pub fn values_until_terminator(self) -> Arg {
+        self.terminator("--")
+    }
+
+    /// Makes an input take every following token as a value until the exact token `token`, which is dropped, or the end of the input.
+    /// This is [`Arg::values_until_terminator()`] with a sentinel other than `--`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("items").terminator("END"), Arg::new().param("rest")]);
+    /// 
+    /// let output = parser.try_get_matches_from(["--items", "a", "--", "c", "END", "more"]).unwrap();
+    /// assert_eq!(output.values_of("items").unwrap(), ["a", "--", "c"]);
+    /// assert_eq!(output.value_of("rest"), Some("more"));
+    /// ```
+    /// A quoted terminator from [`Parser::parse_str()`] is taken as a value.
+    #[must_use]
+    pub fn terminator(self, token: &str) -> Arg {
+        Arg { terminator: Some(String::from(token)), ..self.input_kind() }
+    }
+
+    /// Makes a flag take exactly `n` following positionals as a group, like `--point 3 4`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("point").takes_positionals(2), Arg::new().param("file")]);
+    /// 
+    /// let output = parser.try_get_matches_from(["--point", "3", "4", "plot.svg"]).unwrap();
+    /// assert_eq!(output.group_of("point"), ["3", "4"]);
+    /// assert_eq!(output.value_of("file"), Some("plot.svg"));
+    /// ```
+    /// The grouped tokens don't fill params, and an option or `--` before the group is complete is a [`ParseError::MissingValue`].
+    /// Providing the flag again is handled by [`Parser::on_duplicate()`].
+    #[must_use]
+    pub fn takes_positionals(self, n: usize) -> Arg {
+        self.num_values(n, n)
+    }
+
+    /// Makes a flag take between `min` and `max` following positionals as a group, like [`Arg::takes_positionals()`] with a range.
+    /// Pass `usize::MAX` as `max` for no upper limit, and a `max` below `min` is raised to `min`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("tag").num_values(1, usize::MAX), Arg::new().flag("verbose")]);
+    /// 
+    /// let output = parser.try_get_matches_from(["--tag", "a", "b", "--verbose"]).unwrap();
+    /// assert_eq!(output.group_of("tag"), ["a", "b"]);
+    /// assert!(parser.usage().contains("--tag <tag>..."));
+    /// ```
+    /// The group takes as many tokens as it can, ending early at an option, a `--` or the end of the input.
+    /// It's only a [`ParseError::MissingValue`] if that happens before `min` tokens are taken.
+    #[must_use]
+    pub fn num_values(self, min: usize, max: usize) -> Arg {
+        Arg { positionals: min, max_positionals: max.max(min), ..self.flag_kind() }
+    }
+
+    /// Makes the argument require at least one of `others`, so parsing fails with [`ParseError::MissingRequirement`] if none are provided with it.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, ParseError, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![
+    ///     Arg::new().flag("upload").requires_any(&["token", "password"]),
+    ///     Arg::new().input("token"),
+    ///     Arg::new().input("password"),
+    /// ]);
+    /// 
+    /// assert!(parser.try_get_matches_from(["--upload", "--token", "abc"]).is_ok());
+    /// assert_eq!(parser.try_get_matches_from(["--upload"]), Err(ParseError::MissingRequirement {
+    ///     arg: String::from("upload"),
+    ///     requires: vec![String::from("token"), String::from("password")],
+    /// }));
+    /// ```
+    /// Arguments implied with [`Arg::implies()`] count as provided, but defaults don't.
+    #[must_use]
+    pub fn requires_any(mut self, others: &[&str]) -> Arg {
+        self.requires_any.extend(others.iter().map(|s| String::from(*s)));
+        self
+    }
+
+    /// Makes a flag present when the environment variable `var` is `1`, `true` or `yes`, like `CI=true` enabling `--ci`.
+    /// 
+    /// # Example
+    /// ```no_run
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("ci").env_flag("CI"));
+    /// 
+    /// let output = parser.try_get_matches_from(Vec::<String>::new()).unwrap();
+    /// println!("Running in CI: {}", output.is_present("ci"));
+    /// ```
+    /// The variable is only read if the flag wasn't given, so `-ci` with [`Parser::plus_flags()`] still turns it off.
+    /// Use [`Parser::env_source()`] to read variables from somewhere else.
+    #[must_use]
+    pub fn env_flag(self, var: &str) -> Arg {
+        Arg { env_flag: Some(String::from(var)), ..self }
+    }
+
+    /// Makes the argument imply others, so they are treated as provided whenever this one is.
+    /// Implications are followed transitively, and cycles are allowed.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![
+    ///     Arg::new().flag("all").implies(&["verbose", "recursive"]),
+    ///     Arg::new().flag("verbose"),
+    ///     Arg::new().flag("recursive"),
+    /// ]);
+    /// 
+    /// let output = parser.try_get_matches_from(["--all"]).unwrap();
+    /// assert!(output.is_present("verbose"));
+    /// assert!(output.is_present("recursive"));
+    /// ```
+    /// Passing an implied flag explicitly as well is not a duplicate.
+    #[must_use]
+    pub fn implies(mut self, others: &[&str]) -> Arg {
+        self.implies.extend(others.iter().map(|s| String::from(*s)));
+        self
+    }
+
+    /// Sets the help text shown next to the argument in [`Parser::usage()`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().flag("verbose").help("Print every step");
+    /// ```
+    #[must_use]
+    pub fn help(self, text: &str) -> Arg {
+        Arg { help: Some(String::from(text)), ..self }
+    }
+
+    /// Sets a longer help text shown next to the argument in [`Parser::usage_verbose()`], instead of its [`Arg::help()`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("force").help("Overwrite files").long_help("Overwrite existing files without asking first"));
+    /// 
+    /// assert!(parser.usage().contains("Overwrite files"));
+    /// assert!(parser.usage_verbose().contains("Overwrite existing files without asking first"));
+    /// ```
+    #[must_use]
+    pub fn long_help(self, text: &str) -> Arg {
+        Arg { long_help: Some(String::from(text)), ..self }
+    }
+
+    /// Leaves the default value out of [`Parser::usage()`], e.g. when it's sensitive or long. The default is still used when parsing.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("token").default_value("s3cret").hide_default_value());
+    /// 
+    /// assert!(!parser.usage().contains("s3cret"));
+    /// ```
+    #[must_use]
+    pub fn hide_default_value(self) -> Arg {
+        Arg { hide_default: true, ..self }
+    }
+
+    /// Makes an option match with its own `prefix` instead of `--`, like javac's `-Xlint` from the name `lint` and prefix `-X`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("lint").prefix("-X"));
+    /// 
+    /// let output = parser.try_get_matches_from(["-Xlint=all"]).unwrap();
+    /// assert_eq!(output.value_of("lint"), Some("all"));
+    /// assert!(parser.try_get_matches_from(["--lint=all"]).is_err());
+    /// ```
+    /// Prefixed names are checked before any other option, and the short is still matched as usual.
+    #[must_use]
+    pub fn prefix(self, prefix: &str) -> Arg {
+        Arg { prefix: Some(String::from(prefix)), ..self }
+    }
+
+    /// Sets the section the argument is listed under in [`Parser::usage()`], like `"Output Options"`.
+    /// Without a label, params are listed under `Arguments` and all other arguments under `Options`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().input("format").group_label("Output Options");
+    /// ```
+    #[must_use]
+    pub fn group_label(self, label: &str) -> Arg {
+        Arg { group: Some(String::from(label)), ..self }
+    }
+
+    /// Returns the long name of the argument.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().flag("help");
+    /// assert_eq!(arg.get_name(), "help");
+    /// ```
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the key the argument is stored under after parsing, which is its [`Arg::id()`] or else its long name.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// assert_eq!(Arg::new().flag("help").get_id(), "help");
+    /// assert_eq!(Arg::new().flag("hilfe").id("help").get_id(), "help");
+    /// ```
+    pub fn get_id(&self) -> &str {
+        self.key()
+    }
+
+    fn key(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Returns the long form of the option, which is its name after `--` or its own [`Arg::prefix()`].
+    fn long_option(&self) -> String {
+        format!("{}{}", self.prefix.as_deref().unwrap_or("--"), self.name)
+    }
+
+    /// Marks a param as not yet filled, so it can take a positional again.
+    /// This only matters for arguments managed outside of a [`Parser`], since parsing works on its own copy of the arguments.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let mut arg = Arg::new().param("file");
+    /// arg.reset();
+    /// ```
+    /// Arguments other than params have no state, so this does nothing for them.
+    pub fn reset(&mut self) {
+        if let ArgTypes::Param(_) = self.arg_type {
+            self.set_used(false);
+        }
+    }
+
+    /// Renders the argument as it is listed in [`Parser::usage()`], like `-o, --out <out>`.
+    fn usage_line(&self) -> String {
+        if let ArgTypes::Param(_) = self.arg_type {
+            return format!("<{}>", self.name);
+        }
+
+        let short = match self.short {
+            Some(c) => format!("-{}, ", c),
+            None => String::from("    "),
+        };
+        if self.max_positionals > 0 {
+            let required = format!(" <{}>", self.name).repeat(self.positionals);
+            let optional = match (self.positionals, self.max_positionals) {
+                (0, usize::MAX) => format!(" [<{}>...]", self.name),
+                (_, usize::MAX) => String::from("..."),
+                (min, max) => format!(" [<{}>]", self.name).repeat(max - min),
+            };
+            return format!("{}{}{}{}", short, self.long_option(), required, optional);
+        }
+        let value = match (self.expecting, self.defines, &self.terminator) {
+            (false, _, _) => String::new(),
+            (_, true, _) => String::from(" <key=value>"),
+            (_, _, Some(terminator)) => format!(" <{}>... {}", self.name, terminator),
+            _ if !self.allowed.is_empty() => format!(" <{}>", self.allowed.join("|")),
+            _ => format!(" <{}>", self.name),
+        };
+        format!("{}{}{}", short, self.long_option(), value)
+    }
+
+    /// Splits a value on the [`Arg::value_delimiter()`] and processes each part.
+    fn split_values(&self, value: String) -> Result<Vec<String>, ParseError> {
+        let Some(delimiter) = self.delimiter else {
+            return Ok(vec![self.process_value(value)?]);
+        };
+
+        value.strip_suffix(delimiter)
+            .unwrap_or(&value)
+            .split(delimiter)
+            .filter(|part| self.keep_empty || !part.is_empty())
+            .map(|part| self.process_value(String::from(part)))
+            .collect()
+    }
+
+    fn set_used(&mut self, used: bool) {
+        self.arg_type = ArgTypes::Param(used);
+    }
+
+    fn process_value(&self, value: String) -> Result<String, ParseError> {
+        let value = match &self.map {
+            Some(map) => (map.0)(value),
+            None => value,
+        };
+
+        if self.non_empty && value.is_empty() {
+            return Err(ParseError::Value { name: self.name.clone(), value, kind: ValueErrorKind::Empty });
+        }
+
+        if !self.allowed.is_empty() && !self.allowed.contains(&value) {
+            return Err(ParseError::Value { name: self.name.clone(), value, kind: ValueErrorKind::NotAllowed(self.allowed.clone()) });
+        }
+
+        let (normalized, expected) = match self.value_kind {
+            ValueKind::Text => return Ok(value),
+            ValueKind::ByteSize => (parse_byte_size(&value).map(|n| n.to_string()), "a byte size"),
+            ValueKind::Duration => (parse_duration(&value).map(|d| d.as_millis().to_string()), "a duration"),
+        };
+
+        normalized.ok_or(ParseError::Value { name: self.name.clone(), value, kind: ValueErrorKind::Type(expected) })
+    }
+}
+
+impl Default for Arg {
+    fn default() -> Self {
+        Arg::new()
+    }
+}
+
+impl From<&str> for Arg {
+    /// Creates a flag with the given name, like [`Arg::flag()`].
+    fn from(name: &str) -> Self {
+        Arg::new().flag(name)
+    }
+}
+
+impl From<(&str, char)> for Arg {
+    /// Creates a flag with the given name and short char, like [`Arg::flag()`] and [`Arg::short()`].
+    fn from((name, short): (&str, char)) -> Self {
+        Arg::new().flag(name).short(short)
+    }
+}
+
+/// What a [`Parser`] does when an argument is provided more than once, set with [`Parser::on_duplicate()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Return [`ParseError::Duplicate`]. This is the default.
+    #[default]
+    Error,
+    /// Keep the value of the last occurrence.
+    LastWins,
+    /// Keep the value of the first occurrence and ignore the rest.
+    FirstWins,
+}
+
+/// How unknown long options decide whether they take the token after them, set with [`Parser::catch_all_mode()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CatchAllMode {
+    /// Take the next token as the value, unless it looks like an option. This is the default.
+    #[default]
+    NextValue,
+    /// Never take the next token, so the value is `"true"`.
+    Flag,
+}
+
+/// Builds a value, usually a config struct, out of the arguments found by a [`Parser`].
+/// 
+/// Implement this for your own types and use [`Parser::parse_into_struct()`] to parse straight into them.
+/// 
+/// # Example
+/// ```
+/// # use simple_cli_parser::{Arg, FromMatches, ParseError, ParseOutput, Parser};
+/// struct Config {
+///     name: String,
+///     verbose: bool,
+/// }
+/// 
+/// impl FromMatches for Config {
+///     fn from_matches(m: &ParseOutput) -> Result<Self, ParseError> {
+///         Ok(Config {
+///             name: m.value_of("name").ok_or(ParseError::Missing)?.to_string(),
+///             verbose: m.is_present("verbose"),
+///         })
+///     }
+/// }
+/// 
+/// let parser = Parser::new();
+/// parser.add_args(vec![Arg::new().param("name"), Arg::new().flag("verbose")]);
+/// 
+/// let mut args = vec![String::from("app")].into_iter();
+/// let config: Config = parser.parse_into_struct(&mut args).unwrap();
+/// assert_eq!(config.name, "app");
+/// assert!(!config.verbose);
+/// ```
+pub trait FromMatches: Sized {
+    /// Builds the value from the parsed arguments.
+    fn from_matches(m: &ParseOutput) -> Result<Self, ParseError>;
+}
+
+/// A struct that parses the command line for certain [`Arg`]s.
+/// 
+/// # Example
+/// ```no_run
+/// # use simple_cli_parser::{Arg, Parser};
+/// let parser = Parser::new();
+/// let arg = Arg::new().param("num");
+/// let mut args = std::env::args();
+/// args.next();
+/// 
+/// parser.add_arg(arg);
+/// let output = parser.parse(&mut args).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Parser {
+    args: RefCell<Vec<Arg>>,
+    strict_ordering: Cell<bool>,
+    plus_flags: Cell<bool>,
+    trailing: RefCell<Option<String>>,
+    catch_all: RefCell<Option<String>>,
+    catch_all_mode: Cell<CatchAllMode>,
+    error_formatter: RefCell<Option<ErrorFormatter>>,
+    on_token: RefCell<Option<TokenObserver>>,
+    env_source: RefCell<Option<EnvSource>>,
+    on_duplicate: Cell<DuplicatePolicy>,
+    allow_negative_numbers: Cell<bool>,
+    require_params: Cell<bool>,
+    interspersed: Cell<bool>,
+    param_equals: Cell<bool>,
+    max_args: Cell<Option<usize>>,
+    usage_width: Cell<usize>,
+    before_help: RefCell<Option<String>>,
+    after_help: RefCell<Option<String>>,
+    seeds: RefCell<HashMap<String, String>>,
+    groups: RefCell<Vec<(Vec<String>, bool)>>,
+    together: RefCell<Vec<Vec<String>>>,
+    bin_name: RefCell<Option<String>>,
+    modes: RefCell<Vec<String>>,
+}
+
+impl Parser {
+
+    /// Creates a new Parser struct.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// ```
+    pub fn new() -> Parser {
+        Parser { args: RefCell::new(vec![]), strict_ordering: Cell::new(false), plus_flags: Cell::new(false), trailing: RefCell::new(None), catch_all: RefCell::new(None), catch_all_mode: Cell::new(CatchAllMode::NextValue), error_formatter: RefCell::new(None), on_token: RefCell::new(None), env_source: RefCell::new(None), on_duplicate: Cell::new(DuplicatePolicy::Error), allow_negative_numbers: Cell::new(true), require_params: Cell::new(true), interspersed: Cell::new(true), param_equals: Cell::new(false), max_args: Cell::new(None), usage_width: Cell::new(80), before_help: RefCell::new(None), after_help: RefCell::new(None), seeds: RefCell::new(HashMap::new()), groups: RefCell::new(vec![]), together: RefCell::new(vec![]), bin_name: RefCell::new(None), modes: RefCell::new(vec![]) }
+    }
+
+    /// Creates a new Parser struct with room for at least `n` arguments before reallocating.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::with_capacity(64);
+    /// ```
+    pub fn with_capacity(n: usize) -> Parser {
+        Parser { args: RefCell::new(Vec::with_capacity(n)), ..Parser::new() }
+    }
+
+    /// Reserves room for at least `n` more arguments before reallocating.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.reserve(64);
+    /// ```
+    pub fn reserve(&self, n: usize) {
+        self.args.borrow_mut().reserve(n);
+    }
+
+    /// Adds an argument to the parser.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let arg = Arg::new().param("num");
+    /// let parser = Parser::new();
+    /// 
+    /// parser.add_arg(arg);
+    /// ```
+    /// Anything convertible into an [`Arg`] can be added, so `parser.add_arg("verbose")` adds a `--verbose` flag.
+    /// The argument isn't checked against the rest of the configuration, so call [`Parser::validate()`] once the parser is set up
+    /// to catch clashes, like a shared short or a key used by [`Parser::mode()`].
+    pub fn add_arg(&self, arg: impl Into<Arg>) {
+        self.push_arg(arg.into());
+    }
+
+    /// Adds a vector of arguments to the parser.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let args = vec![
+    ///     Arg::new().param("num1"),
+    ///     Arg::new().param("num2"),
+    /// ];
+    /// let parser = Parser::new();
+    /// 
+    /// parser.add_args(args);
+    /// ```
+    /// Like [`Parser::add_arg()`], the arguments are only checked by [`Parser::validate()`].
+    pub fn add_args<T: Into<Arg>>(&self, args: Vec<T>) {
+        for arg in args {
+            self.push_arg(arg.into());
+        }
+    }
+
+    /// Adds an argument, first picking its short if it was set with [`Arg::auto_short()`].
+    fn push_arg(&self, mut arg: Arg) {
+        let mut args = self.args.borrow_mut();
+        if arg.auto_short && arg.short.is_none() {
+            arg.short = arg.name.chars().next().filter(|c| !args.iter().any(|a| a.short == Some(*c)));
+        }
+        args.push(arg);
+    }
+
+    /// Appends the arguments of another parser to this one.
+    /// Settings enabled on `other` (like [`Parser::strict_ordering()`]) are enabled on this parser as well.
+    /// 
+    /// Returns an error without modifying this parser if the combined arguments fail the checks of [`Parser::validate()`],
+    /// like an argument in `other` sharing a name or short char with one already added.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("verbose"));
+    /// 
+    /// let network = Parser::new();
+    /// network.add_arg(Arg::new().input("port"));
+    /// 
+    /// parser.merge(&network).unwrap();
+    /// assert_eq!(parser.len(), 2);
+    /// ```
+    pub fn merge(&self, other: &Parser) -> Result<(), ConfigError> {
+        let mut args = other.args();
+        let combined: Vec<Arg> = self.args().into_iter().chain(args.iter().cloned()).collect();
+        self.check_args(&combined)?;
+
+        self.args.borrow_mut().append(&mut args);
+        self.strict_ordering.set(self.strict_ordering.get() || other.strict_ordering.get());
+        self.plus_flags.set(self.plus_flags.get() || other.plus_flags.get());
+        if self.trailing.borrow().is_none() {
+            self.trailing.replace(other.trailing.borrow().clone());
+        }
+        if self.catch_all.borrow().is_none() {
+            self.catch_all.replace(other.catch_all.borrow().clone());
+            self.catch_all_mode.set(other.catch_all_mode.get());
+        }
+        if self.error_formatter.borrow().is_none() {
+            self.error_formatter.replace(other.error_formatter.borrow().clone());
+        }
+        if self.on_token.borrow().is_none() {
+            self.on_token.replace(other.on_token.borrow().clone());
+        }
+        if self.env_source.borrow().is_none() {
+            self.env_source.replace(other.env_source.borrow().clone());
+        }
+        if self.before_help.borrow().is_none() {
+            self.before_help.replace(other.before_help.borrow().clone());
+        }
+        if self.after_help.borrow().is_none() {
+            self.after_help.replace(other.after_help.borrow().clone());
+        }
+        if self.bin_name.borrow().is_none() {
+            self.bin_name.replace(other.bin_name.borrow().clone());
+        }
+        if self.modes.borrow().is_empty() {
+            self.modes.replace(other.modes.borrow().clone());
+        }
+        self.allow_negative_numbers.set(self.allow_negative_numbers.get() && other.allow_negative_numbers.get());
+        self.require_params.set(self.require_params.get() && other.require_params.get());
+        self.interspersed.set(self.interspersed.get() && other.interspersed.get());
+        self.param_equals.set(self.param_equals.get() || other.param_equals.get());
+        if self.max_args.get().is_none() {
+            self.max_args.set(other.max_args.get());
+        }
+        for (name, value) in other.seeds.borrow().iter() {
+            self.seeds.borrow_mut().entry(name.clone()).or_insert_with(|| value.clone());
+        }
+        self.groups.borrow_mut().extend(other.groups.borrow().iter().cloned());
+        self.together.borrow_mut().extend(other.together.borrow().iter().cloned());
+        if self.on_duplicate.get() == DuplicatePolicy::Error {
+            self.on_duplicate.set(other.on_duplicate.get());
+        }
+        Ok(())
+    }
+
+    /// Returns the arguments associated with this parser as a vector.
+    /// The arguments are always in the order they were added to the parser.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// # let parser = Parser::new();
+    /// let args = parser.args();
+    /// ```
+    pub fn args(&self) -> Vec<Arg> {
+        self.args.borrow().clone()
+    }
+
+    /// Returns the arguments associated with this parser sorted by their long name.
+    /// Arguments with the same name keep the order they were added in.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("verbose"), Arg::new().input("config")]);
+    /// 
+    /// let args = parser.args_sorted();
+    /// assert_eq!(args[0].get_name(), "config");
+    /// ```
+    pub fn args_sorted(&self) -> Vec<Arg> {
+        let mut args = self.args();
+        args.sort_by(|a, b| a.name.cmp(&b.name));
+        args
+    }
+
+    /// Returns the number of arguments associated with this parser.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// # let parser = Parser::new();
+    /// let n = parser.len();
+    /// ```
+    pub fn len(&self) -> usize {
+        self.args.borrow().len()
+    }
+
+    /// Returns `true` if no arguments have been added to this parser.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// assert!(parser.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.args.borrow().is_empty()
+    }
+
+    /// Requires all options to come before any positional arguments.
+    /// When enabled, an option appearing after a param has been consumed is an error.
+    /// This is disabled by default, allowing options and params to be interleaved.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.strict_ordering(true);
+    /// ```
+    pub fn strict_ordering(&self, strict: bool) {
+        self.strict_ordering.set(strict);
+    }
+
+    /// Sets whether options and positionals can be mixed freely.
+    /// When disabled, the first positional ends option parsing and every token after it is positional, even `--`.
+    /// This is enabled by default.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().param("cmd"), Arg::new().param("arg"), Arg::new().flag("verbose")]);
+    /// parser.parse_interspersed(false);
+    /// 
+    /// let output = parser.try_get_matches_from(["--verbose", "grep", "--verbose"]).unwrap();
+    /// assert_eq!(output.value_of("arg"), Some("--verbose"));
+    /// ```
+    /// Unlike [`Parser::strict_ordering()`], options after a positional are taken as positionals rather than rejected.
+    pub fn parse_interspersed(&self, interspersed: bool) {
+        self.interspersed.set(interspersed);
+    }
+
+    /// Limits how many tokens can be parsed, so parsing fails with [`ParseError::TooManyArgs`] on more.
+    /// This guards against unbounded work on untrusted input, and there is no limit by default.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, ParseError, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("verbose").short('v').count());
+    /// parser.max_args(2);
+    /// 
+    /// assert!(parser.try_get_matches_from(["-v", "-v"]).is_ok());
+    /// assert_eq!(parser.try_get_matches_from(["-v", "-v", "-v"]), Err(ParseError::TooManyArgs { limit: 2 }));
+    /// ```
+    /// Iterators are only read up to one token past the limit.
+    pub fn max_args(&self, limit: usize) {
+        self.max_args.set(Some(limit));
+    }
+
+    /// Makes the arguments called `names` exclusive, so at most one of them can be given, like a set of radio buttons.
+    /// When `required` is true exactly one must be given, otherwise none is fine too.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, GroupViolationKind, ParseError, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("json"), Arg::new().flag("yaml"), Arg::new().flag("toml")]);
+    /// parser.exclusive_group(&["json", "yaml", "toml"], true);
+    /// 
+    /// assert!(parser.try_get_matches_from(["--yaml"]).is_ok());
+    /// let err = parser.try_get_matches_from(["--json", "--toml"]).unwrap_err();
+    /// assert!(matches!(err, ParseError::GroupViolation { reason: GroupViolationKind::TooMany, .. }));
+    /// ```
+    /// Defaults don't count as giving an argument, and [`Arg::disables_validation()`] skips the check.
+    /// [`Parser::validate()`] reports members which aren't registered arguments.
+    pub fn exclusive_group(&self, names: &[&str], required: bool) {
+        let names = names.iter().map(|name| String::from(*name)).collect();
+        self.groups.borrow_mut().push((names, required));
+    }
+
+    /// Makes the arguments called `members` go together, so either all of them are given or none are, like `--username` and `--password`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, ParseError, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("username"), Arg::new().input("password")]);
+    /// parser.group_all_or_none(&["username", "password"]);
+    /// 
+    /// assert!(parser.try_get_matches_from(Vec::<String>::new()).is_ok());
+    /// assert!(parser.try_get_matches_from(["--username", "me", "--password", "hunter2"]).is_ok());
+    /// assert_eq!(parser.try_get_matches_from(["--username", "me"]), Err(ParseError::PartialGroup {
+    ///     group: vec![String::from("username"), String::from("password")],
+    ///     present: vec![String::from("username")],
+    ///     missing: vec![String::from("password")],
+    /// }));
+    /// ```
+    /// Like [`Parser::exclusive_group()`], defaults don't count as giving an argument,
+    /// and [`Parser::validate()`] reports members which aren't registered arguments.
+    pub fn group_all_or_none(&self, members: &[&str]) {
+        let members = members.iter().map(|name| String::from(*name)).collect();
+        self.together.borrow_mut().push(members);
+    }
+
+    /// Sets whether a positional like `file=a.txt` fills the param named `file` with `a.txt`, wherever it appears.
+    /// This is disabled by default, so the whole token is the value of the next param.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().param("src"), Arg::new().param("dest")]);
+    /// parser.param_equals_as_value(true);
+    /// 
+    /// let output = parser.try_get_matches_from(["dest=b.txt", "a.txt"]).unwrap();
+    /// assert_eq!(output.value_of("src"), Some("a.txt"));
+    /// assert_eq!(output.value_of("dest"), Some("b.txt"));
+    /// ```
+    /// Other positionals fill the params which are still empty in order, so a param should be named before it would be filled.
+    /// Tokens whose part before the `=` isn't the name of a param are plain positionals.
+    pub fn param_equals_as_value(&self, enabled: bool) {
+        self.param_equals.set(enabled);
+    }
+
+    /// Enables `+name`/`-name` style flags, where `+` enables a flag and a single `-` disables it.
+    /// Flags called this way are stored with the value `"true"` or `"false"`, while `--name` still stores no value.
+    /// Both the long name and the short char can be used, e.g. `+x` and `-x`.
+    /// This is disabled by default.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("trace").short('x'));
+    /// parser.plus_flags(true);
+    /// 
+    /// let mut args = vec![String::from("+x")].into_iter();
+    /// let hashmap = parser.parse(&mut args).unwrap();
+    /// assert_eq!(hashmap.get("trace").unwrap(), &Some(String::from("true")));
+    /// ```
+    pub fn plus_flags(&self, enabled: bool) {
+        self.plus_flags.set(enabled);
+    }
+
+    /// Collects every token after a bare `--` under `name`, instead of treating them as positionals.
+    /// The tokens are available through [`ParseOutput::values_of()`], and are never parsed as options.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().param("cmd"));
+    /// parser.trailing_var_arg("args");
+    /// 
+    /// let output = parser.try_get_matches_from(["run", "--", "--inner", "-x"]).unwrap();
+    /// assert_eq!(output.values_of("args").unwrap(), ["--inner", "-x"]);
+    /// ```
+    pub fn trailing_var_arg(&self, name: &str) {
+        self.trailing.replace(Some(String::from(name)));
+    }
+
+    /// Requires the first token to be one of `names`, which is stored under the key `mode`, like `tool build` or `tool test`.
+    /// This is lighter than separate parsers when every mode takes the same options.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, ParseError, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("release"));
+    /// parser.mode(&["build", "test"]).unwrap();
+    /// 
+    /// let output = parser.try_get_matches_from(["build", "--release"]).unwrap();
+    /// assert_eq!(output.value_of("mode"), Some("build"));
+    /// assert_eq!(parser.try_get_matches_from(["--release", "build"]), Err(ParseError::InvalidMode {
+    ///     got: String::from("--release"),
+    ///     allowed: vec![String::from("build"), String::from("test")],
+    /// }));
+    /// ```
+    /// Running the command without any tokens is a [`ParseError::MissingRequired`] naming `mode`.
+    /// 
+    /// # Errors
+    /// Returns [`ConfigError::DuplicateKey`] if an argument is already stored under the key `mode`,
+    /// since its value would overwrite the mode. Arguments added afterwards aren't checked here,
+    /// so [`Parser::validate()`] has to be called to catch those, the same as any other clash between arguments.
+    pub fn mode(&self, names: &[&str]) -> Result<(), ConfigError> {
+        if self.args.borrow().iter().any(|arg| arg.key() == "mode") {
+            return Err(ConfigError::DuplicateKey(String::from("mode")));
+        }
+        self.modes.replace(names.iter().map(|name| String::from(*name)).collect());
+        Ok(())
+    }
+
+    /// Sets the program name shown by [`Parser::usage_line()`].
+    /// Without one, the file name of the running executable is used.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.bin_name("prog");
+    /// 
+    /// assert_eq!(parser.usage_line(), "Usage: prog");
+    /// ```
+    pub fn bin_name(&self, name: &str) {
+        self.bin_name.replace(Some(String::from(name)));
+    }
+
+    /// Collects unknown long options like `--key value` or `--key=value` under `name`, instead of returning an error.
+    /// The options are available by their long name through [`ParseOutput::extras()`].
+    /// Whether `--key` takes the token after it is set with [`Parser::catch_all_mode()`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("verbose"));
+    /// parser.catch_all("extras");
+    /// 
+    /// let output = parser.try_get_matches_from(["--color", "red", "--verbose", "--size=10"]).unwrap();
+    /// let extras = output.extras();
+    /// assert_eq!(extras["color"], "red");
+    /// assert_eq!(extras["size"], "10");
+    /// ```
+    pub fn catch_all(&self, name: &str) {
+        self.catch_all.replace(Some(String::from(name)));
+    }
+
+    /// Sets whether unknown options collected by [`Parser::catch_all()`] take the token after them.
+    /// By default this is [`CatchAllMode::NextValue`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, CatchAllMode, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().param("file"));
+    /// parser.catch_all("extras");
+    /// parser.catch_all_mode(CatchAllMode::Flag);
+    /// 
+    /// let output = parser.try_get_matches_from(["--fast", "a.txt"]).unwrap();
+    /// assert_eq!(output.extras()["fast"], "true");
+    /// assert_eq!(output.value_of("file"), Some("a.txt"));
+    /// ```
+    pub fn catch_all_mode(&self, mode: CatchAllMode) {
+        self.catch_all_mode.set(mode);
+    }
+
+    /// Sets how [`Parser::format_error()`] renders errors, e.g. to translate them.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, ParseError, Parser};
+    /// let parser = Parser::new();
+    /// parser.error_formatter(|err| match err {
+    ///     ParseError::Unexpected(token) => format!("Argument inattendu '{}'", token),
+    ///     err => err.to_string(),
+    /// });
+    /// 
+    /// let err = parser.try_get_matches_from(["--nope"]).unwrap_err();
+    /// assert_eq!(parser.format_error(&err), "Argument inattendu '--nope'");
+    /// ```
+    pub fn error_formatter(&self, f: impl Fn(&ParseError) -> String + 'static) {
+        self.error_formatter.replace(Some(ErrorFormatter(Rc::new(f))));
+    }
+
+    /// Sets a callback which is given each token and its [`Classification`] as it is parsed, e.g. for logging.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Classification, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("verbose"), Arg::new().param("file")]);
+    /// parser.on_token(|token, classification| println!("{}: {:?}", token, classification));
+    /// 
+    /// parser.try_get_matches_from(["--verbose", "a.txt"]).unwrap();
+    /// ```
+    /// Each token is reported as parsing takes it, so the tokens after one that fails to parse aren't reported.
+    /// A parse started from within `f` doesn't report its own tokens.
+    pub fn on_token(&self, f: impl FnMut(&str, Classification) + 'static) {
+        self.on_token.replace(Some(TokenObserver(Rc::new(RefCell::new(f)))));
+    }
+
+    /// Sets where environment variables are read from for [`Arg::env_flag()`], instead of the process environment.
+    /// This is mostly useful to make tests independent of the environment they run in.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("ci").env_flag("CI"));
+    /// parser.env_source(|var| (var == "CI").then(|| String::from("true")));
+    /// 
+    /// let output = parser.try_get_matches_from(Vec::<String>::new()).unwrap();
+    /// assert!(output.is_present("ci"));
+    /// ```
+    pub fn env_source(&self, f: impl Fn(&str) -> Option<String> + 'static) {
+        self.env_source.replace(Some(EnvSource(Rc::new(f))));
+    }
+
+    /// Renders an error with the formatter set by [`Parser::error_formatter()`].
+    /// Without one, this is the same as the error's English [`Display`] output.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// let err = parser.try_get_matches_from(["--nope"]).unwrap_err();
+    /// assert_eq!(parser.format_error(&err), "Invalid command, unexpected token '--nope'");
+    /// ```
+    pub fn format_error(&self, err: &ParseError) -> String {
+        // The formatter is cloned out so it can change the parser's settings
+        let formatter = self.error_formatter.borrow().clone();
+        match formatter {
+            Some(f) => (f.0)(err),
+            None => err.to_string(),
+        }
+    }
+
+    /// Sets what happens when an argument is provided more than once.
+    /// By default this is [`DuplicatePolicy::Error`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, DuplicatePolicy, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().input("level"));
+    /// parser.on_duplicate(DuplicatePolicy::LastWins);
+    /// 
+    /// let output = parser.try_get_matches_from(["--level", "info", "--level", "debug"]).unwrap();
+    /// assert_eq!(output.value_of("level"), Some("debug"));
+    /// ```
+    pub fn on_duplicate(&self, policy: DuplicatePolicy) {
+        self.on_duplicate.set(policy);
+    }
+
+    /// Allows negative numbers like `-5` or `-3.14` to be used as values and positionals.
+    /// A negative number is only treated as an option if an argument is registered with it, like `short('5')`.
+    /// This is enabled by default, when disabled any token starting with `-` is an option.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("offset"), Arg::new().param("num")]);
+    /// 
+    /// let output = parser.try_get_matches_from(["--offset", "-2", "-3.14"]).unwrap();
+    /// assert_eq!(output.value_of("offset"), Some("-2"));
+    /// assert_eq!(output.value_of("num"), Some("-3.14"));
+    /// ```
+    pub fn allow_negative_numbers(&self, allow: bool) {
+        self.allow_negative_numbers.set(allow);
+    }
+
+    /// Sets whether every param must be provided.
+    /// When disabled, missing params are not an error and simply don't appear in the output.
+    /// This is enabled by default.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().param("src"), Arg::new().param("dest")]);
+    /// parser.require_params(false);
+    /// 
+    /// let output = parser.try_get_matches_from(["a.txt"]).unwrap();
+    /// assert_eq!(output.value_of("src"), Some("a.txt"));
+    /// assert!(!output.is_present("dest"));
+    /// ```
+    pub fn require_params(&self, require: bool) {
+        self.require_params.set(require);
+    }
+
+    /// Checks that the parser's configuration is consistent, so mistakes can be caught before parsing.
+    /// This checks that no long names, aliases, short chars or keys are shared between arguments, whatever their kind,
+    /// that no name is also the namespace of a dotted name (see [`ParseOutput::namespace()`]),
+    /// and that arguments and groups only refer to registered arguments.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, ConfigError, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("verbose").short('v'), Arg::new().flag("version").short('v')]);
+    /// 
+    /// assert_eq!(parser.validate(), Err(ConfigError::DuplicateShort('v')));
+    /// ```
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.check_args(&self.args.borrow())
+    }
+
+    /// Runs the checks of [`Parser::validate()`] on `args`, so a merge can be checked before it's applied.
+    fn check_args(&self, args: &[Arg]) -> Result<(), ConfigError> {
+        let mut names: Vec<&str> = vec![];
+        let mut shorts: Vec<char> = vec![];
+
+        for arg in args.iter() {
+            for name in std::iter::once(&arg.name).chain(&arg.aliases) {
+                if names.contains(&name.as_str()) {
+                    return Err(ConfigError::DuplicateName(name.clone()));
+                }
+                names.push(name);
+            }
+
+            if let Some(short) = arg.short {
+                if shorts.contains(&short) {
+                    return Err(ConfigError::DuplicateShort(short));
+                }
+                shorts.push(short);
+            }
+        }
+
+        for name in &names {
+            let mut prefix = *name;
+            while let Some((namespace, _)) = prefix.rsplit_once('.') {
+                if names.contains(&namespace) {
+                    return Err(ConfigError::NamespaceCollision(String::from(namespace)));
+                }
+                prefix = namespace;
+            }
+        }
+
+        // The mode is stored under its own key
+        let mut keys: Vec<&str> = vec![];
+        if !self.modes.borrow().is_empty() {
+            keys.push("mode");
+        }
+        for arg in args.iter() {
+            if keys.contains(&arg.key()) {
+                return Err(ConfigError::DuplicateKey(String::from(arg.key())));
+            }
+            keys.push(arg.key());
+        }
+
+        for arg in args.iter() {
+            for reference in arg.implies.iter().chain(&arg.requires_any).chain(&arg.required_unless) {
+                if !args.iter().any(|a| a.key() == reference) {
+                    return Err(ConfigError::UnknownReference { arg: arg.name.clone(), reference: reference.clone() });
+                }
+            }
+        }
+
+        let groups = self.groups.borrow();
+        let together = self.together.borrow();
+        for group in groups.iter().map(|(group, _)| group).chain(together.iter()) {
+            if let Some(member) = group.iter().find(|member| !keys.contains(&member.as_str())) {
+                return Err(ConfigError::UnknownMember { group: group.clone(), member: member.clone() });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Seeds default values for arguments, e.g. from a config file loaded by the caller.
+    /// Values given on the command line override these, and seeds take precedence over [`Arg::default_with()`].
+    /// Names which don't belong to a registered argument are ignored.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// # use std::collections::HashMap;
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("host"), Arg::new().input("port")]);
+    /// parser.with_defaults_from(HashMap::from([
+    ///     (String::from("host"), String::from("localhost")),
+    ///     (String::from("port"), String::from("8080")),
+    /// ]));
+    /// 
+    /// let output = parser.try_get_matches_from(["--port", "9000"]).unwrap();
+    /// assert_eq!(output.value_of("host"), Some("localhost"));
+    /// assert_eq!(output.value_of("port"), Some("9000"));
+    /// ```
+    pub fn with_defaults_from(&self, map: HashMap<String, String>) {
+        self.seeds.borrow_mut().extend(map);
+    }
+
+    /// Renders the one-line synopsis of the command, like `Usage: prog [OPTIONS] <file>`, e.g. to print on a usage error.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("verbose").short('v'), Arg::new().param("src"), Arg::new().param("dest")]);
+    /// parser.bin_name("cp");
+    /// 
+    /// assert_eq!(parser.usage_line(), "Usage: cp [OPTIONS] <src> <dest>");
+    /// ```
+    /// Params are shown as `[name]` when [`Parser::require_params()`] is disabled,
+    /// and a [`Parser::trailing_var_arg()`] is shown last as `[-- <name>...]`.
+    pub fn usage_line(&self) -> String {
+        let name = self.bin_name.borrow().clone().or_else(|| {
+            let path = std::env::args().next()?;
+            Some(std::path::Path::new(&path).file_name()?.to_string_lossy().into_owned())
+        });
+        let args = self.args.borrow();
+
+        let mut parts = vec![String::from("Usage:")];
+        parts.extend(name);
+        if args.iter().any(|arg| !matches!(arg.arg_type, ArgTypes::Param(_))) {
+            parts.push(String::from("[OPTIONS]"));
+        }
+        for arg in args.iter().filter(|arg| matches!(arg.arg_type, ArgTypes::Param(_))) {
+            if self.require_params.get() {
+                parts.push(format!("<{}>", arg.name));
+            } else {
+                parts.push(format!("[{}]", arg.name));
+            }
+        }
+        if let Some(trailing) = &*self.trailing.borrow() {
+            parts.push(format!("[-- <{}>...]", trailing));
+        }
+        parts.join(" ")
+    }
+
+    /// Renders a listing of the parser's arguments, split into sections by [`Arg::group_label()`].
+    /// Sections appear in the order their first argument was added, and arguments within a section keep the order they were added in.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![
+    ///     Arg::new().param("file"),
+    ///     Arg::new().flag("verbose").short('v'),
+    ///     Arg::new().input("format").group_label("Output Options"),
+    /// ]);
+    /// 
+    /// let usage = parser.usage();
+    /// assert!(usage.starts_with("Arguments:\n  <file>\n"));
+    /// assert!(usage.contains("Options:\n  -v, --verbose\n"));
+    /// assert!(usage.contains("Output Options:\n      --format <format>\n"));
+    /// ```
+    /// Each argument's [`Arg::help()`] text is listed next to it, followed by its default value as `[default: value]`.
+    /// Defaults from [`Arg::default_with()`] aren't computed just to be shown, so only seeds and [`Arg::default_value()`] are listed.
+    pub fn usage(&self) -> String {
+        self.render_usage(false)
+    }
+
+    /// Renders the listing of [`Parser::usage()`] with each argument's [`Arg::long_help()`], e.g. for a detailed `--help`.
+    /// Arguments without a long help show their [`Arg::help()`] instead.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![
+    ///     Arg::new().flag("all").help("Show everything").long_help("Show hidden files as well"),
+    ///     Arg::new().flag("list").help("Use a long listing format"),
+    /// ]);
+    /// 
+    /// assert_eq!(parser.usage_verbose(), "Options:\n      --all   Show hidden files as well\n      --list  Use a long listing format\n");
+    /// ```
+    pub fn usage_verbose(&self) -> String {
+        self.render_usage(true)
+    }
+
+    /// Renders the listing for [`Parser::usage()`], or for [`Parser::usage_verbose()`] when `long` is true.
+    fn render_usage(&self, long: bool) -> String {
+        let mut sections: Vec<(&str, Vec<&Arg>)> = vec![];
+        let args = self.args.borrow();
+
+        for arg in args.iter() {
+            let label = match (&arg.group, &arg.arg_type) {
+                (Some(group), _) => group.as_str(),
+                (None, ArgTypes::Param(_)) => "Arguments",
+                (None, _) => "Options",
+            };
+            match sections.iter_mut().find(|(l, _)| *l == label) {
+                Some((_, section)) => section.push(arg),
+                None => sections.push((label, vec![arg])),
+            }
+        }
+
+        // Help texts start in the same column across every section
+        let column = args.iter().map(|arg| arg.usage_line().chars().count()).max().unwrap_or(0) + 4;
+        let help_width = self.usage_width.get().saturating_sub(column).max(1);
+
+        let sections: Vec<String> = sections.into_iter()
+            .map(|(label, section)| {
+                let mut out = format!("{}:\n", label);
+                for arg in section {
+                    let line = format!("  {}", arg.usage_line());
+                    let Some(help) = self.help_text(arg, long) else {
+                        out.push_str(&format!("{}\n", line));
+                        continue;
+                    };
+                    for (i, help_line) in wrap(&help, help_width).into_iter().enumerate() {
+                        let start = if i == 0 { line.as_str() } else { "" };
+                        out.push_str(&format!("{:<column$}{}\n", start, help_line));
+                    }
+                }
+                out
+            })
+            .collect();
+        let mut usage = sections.join("\n");
+        if let Some(before) = &*self.before_help.borrow() {
+            usage = format!("{}\n\n{}", before, usage);
+        }
+        if let Some(after) = &*self.after_help.borrow() {
+            usage = format!("{}\n{}\n", usage, after);
+        }
+        usage
+    }
+
+    /// Returns the text listed next to an argument by [`Parser::usage()`], which is its help and its default value.
+    /// With `long`, the [`Arg::long_help()`] is used if there is one.
+    fn help_text(&self, arg: &Arg, long: bool) -> Option<String> {
+        let default = match arg.hide_default {
+            true => None,
+            false => self.seeds.borrow().get(arg.key()).cloned().or_else(|| arg.default_value.clone().filter(|_| arg.default.is_none())),
+        };
+        let help = match &arg.long_help {
+            Some(long_help) if long => Some(long_help),
+            _ => arg.help.as_ref(),
+        };
+
+        match (help, default) {
+            (Some(help), Some(default)) => Some(format!("{} [default: {}]", help, default)),
+            (None, Some(default)) => Some(format!("[default: {}]", default)),
+            (help, None) => help.cloned(),
+        }
+    }
+
+    /// Sets text shown by [`Parser::usage()`] before the listing of arguments, like a description of the program.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("verbose"));
+    /// parser.before_help("Copies files.");
+    /// 
+    /// assert_eq!(parser.usage(), "Copies files.\n\nOptions:\n      --verbose\n");
+    /// ```
+    pub fn before_help(&self, text: &str) {
+        self.before_help.replace(Some(String::from(text)));
+    }
+
+    /// Sets text shown by [`Parser::usage()`] after the listing of arguments, like examples or notes.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("verbose"));
+    /// parser.after_help("Example: cp --verbose a b");
+    /// 
+    /// assert_eq!(parser.usage(), "Options:\n      --verbose\n\nExample: cp --verbose a b\n");
+    /// ```
+    pub fn after_help(&self, text: &str) {
+        self.after_help.replace(Some(String::from(text)));
+    }
+
+    /// Sets the column [`Parser::usage()`] wraps help texts at, e.g. to fit the terminal. By default this is 80.
+    /// Options stay in their own column, and only the help texts next to them are wrapped.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("verbose").help("Print every step"));
+    /// parser.usage_width(30);
+    /// 
+    /// assert_eq!(parser.usage(), "Options:\n      --verbose  Print every\n                 step\n");
+    /// ```
+    /// Words longer than the space left for help are put on their own line rather than split.
+    pub fn usage_width(&self, width: usize) {
+        self.usage_width.set(width);
+    }
+
+    /// Returns the options which `partial` could be completed to, e.g. for an interactive prompt.
+    /// A partial starting with `--` completes to long names and aliases, and one starting with `-` completes to shorts.
+    /// Anything else has no completions, since params can take any value.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("verbose").short('v'), Arg::new().flag("version"), Arg::new().input("out").short('o')]);
+    /// 
+    /// assert_eq!(parser.complete("--ver"), vec!["--verbose", "--version"]);
+    /// assert_eq!(parser.complete("-"), vec!["-v", "-o"]);
+    /// ```
+    /// Completions are in the order the arguments were added.
+    pub fn complete(&self, partial: &str) -> Vec<String> {
+        let args = self.args.borrow();
+
+        if let Some(prefix) = partial.strip_prefix("--") {
+            args.iter()
+                .filter(|arg| !matches!(arg.arg_type, ArgTypes::Param(_)) && arg.prefix.is_none())
+                .flat_map(|arg| std::iter::once(&arg.name).chain(&arg.aliases))
+                .filter(|name| name.starts_with(prefix))
+                .map(|name| format!("--{}", name))
+                .collect()
+        } else if let Some(prefix) = partial.strip_prefix('-') {
+            args.iter()
+                .filter_map(|arg| arg.short)
+                .map(|c| format!("-{}", c))
+                .filter(|short| short[1..].starts_with(prefix))
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
+    /// Returns the names of the arguments which must be given, in the order they were added.
+    /// These are the params, unless [`Parser::require_params()`] is disabled, and any options marked with [`Arg::required()`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().param("src"), Arg::new().flag("verbose"), Arg::new().input("out").required(true)]);
+    /// 
+    /// assert_eq!(parser.required_args(), vec!["src", "out"]);
+    /// ```
+    pub fn required_args(&self) -> Vec<String> {
+        self.args.borrow()
+            .iter()
+            .filter(|arg| arg.required || (matches!(arg.arg_type, ArgTypes::Param(_)) && self.require_params.get()))
+            .map(|arg| arg.name.clone())
+            .collect()
+    }
+
+    /// Returns the names of the required arguments which are not in `provided`, without parsing.
+    /// These are the arguments listed by [`Parser::required_args()`], so params only count while [`Parser::require_params()`] is enabled.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().param("src"), Arg::new().param("dest")]);
+    /// 
+    /// assert_eq!(parser.missing_required(&["src"]), vec!["dest"]);
+    /// ```
+    pub fn missing_required(&self, provided: &[&str]) -> Vec<String> {
+        self.args.borrow()
+            .iter()
+            .filter(|arg| arg.required || (matches!(arg.arg_type, ArgTypes::Param(_)) && self.require_params.get()))
+            .filter(|arg| !provided.contains(&arg.name.as_str()))
+            .filter(|arg| !arg.required_unless.iter().any(|other| provided.contains(&other.as_str())))
+            .map(|arg| arg.name.clone())
+            .collect()
+    }
+
+    /// Returns the pairs of names in `provided` which can't be given together, without parsing.
+    /// Two arguments conflict when they are in the same [`Parser::exclusive_group()`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("json"), Arg::new().flag("yaml"), Arg::new().flag("color")]);
+    /// parser.exclusive_group(&["json", "yaml"], false);
+    /// 
+    /// assert_eq!(parser.find_conflicts(&["color", "yaml", "json"]), vec![(String::from("json"), String::from("yaml"))]);
+    /// ```
+    /// Each pair is listed once, in the order its names were given to the group.
+    pub fn find_conflicts(&self, provided: &[&str]) -> Vec<(String, String)> {
+        let mut conflicts = vec![];
+        for (members, _) in self.groups.borrow().iter() {
+            let given: Vec<&String> = members.iter().filter(|member| provided.contains(&member.as_str())).collect();
+            for (i, first) in given.iter().enumerate() {
+                for second in &given[i + 1..] {
+                    let pair = (String::from(*first), String::from(*second));
+                    if !conflicts.contains(&pair) {
+                        conflicts.push(pair);
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Reports how each token would be interpreted when parsed, without returning any errors.
+    /// This is useful for debugging why an argument isn't being picked up.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Classification, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("out").short('o'), Arg::new().param("file")]);
+    /// 
+    /// let explained = parser.explain(["-o", "a.txt", "b.txt"]);
+    /// assert_eq!(explained[0].1, Classification::ShortOption('o'));
+    /// assert_eq!(explained[1].1, Classification::Value(String::from("out")));
+    /// assert_eq!(explained[2].1, Classification::Positional(String::from("file")));
+    /// ```
+    pub fn explain<I, T>(&self, args: I) -> Vec<(String, Classification)>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let parser_args = self.args();
+        let mut reader = Reader::new(self, &parser_args);
+        args.into_iter()
+            .map(Into::into)
+            .map(|token| {
+                let reading = reader.read(&token, false);
+                let classification = reader.classify(&token, reading);
+                (token, classification)
+            })
+            .collect()
+    }
+
+    /// Stores the value `process` makes out of `token`, applying the duplicate policy.
+    /// The token is moved rather than cloned, since it is either the stored value or the error.
+    /// Returns whether the value was stored, which it isn't for a later duplicate with [`DuplicatePolicy::FirstWins`].
+    fn insert_value(
+        &self,
+        hashmap: &mut HashMap<String, Option<String>>,
+        name: String,
+        token: String,
+        process: impl FnOnce(String) -> Result<Option<String>, ParseError>,
+    ) -> Result<bool, ParseError> {
+        if hashmap.contains_key(&name) {
+            match self.on_duplicate.get() {
+                DuplicatePolicy::Error => return Err(ParseError::Duplicate(token)),
+                DuplicatePolicy::FirstWins => return Ok(false),
+                DuplicatePolicy::LastWins => {},
+            }
+        }
+
+        hashmap.insert(name, process(token)?);
+        Ok(true)
+    }
+
+    /// Stores the values of an argument which takes several at once, marking it as present like [`Parser::insert_value()`].
+    fn insert_list(
+        &self,
+        hashmap: &mut HashMap<String, Option<String>>,
+        lists: &mut HashMap<String, Vec<String>>,
+        arg: &Arg,
+        token: String,
+        values: Vec<String>,
+    ) -> Result<bool, ParseError> {
+        let stored = self.insert_value(hashmap, String::from(arg.key()), token, |_| Ok(None))?;
+        if stored {
+            lists.insert(String::from(arg.key()), values);
+        }
+        Ok(stored)
+    }
+
+    /// Splits the value of an [`Arg::defines()`] argument on its first `=` and stores the pair.
+    fn insert_define(&self, defines: &mut HashMap<String, HashMap<String, String>>, arg: &Arg, token: String) -> Result<(), ParseError> {
+        let pair = arg.process_value(token)?;
+        let (key, value) = pair.split_once('=').unwrap_or((&pair, "true"));
+
+        let (normalized, expected) = match arg.define_schema.iter().find(|(k, _)| k == key).map(|(_, kind)| kind) {
+            None if arg.deny_unknown_keys => {
+                return Err(ParseError::Value { name: arg.name.clone(), value: pair, kind: ValueErrorKind::UnknownKey });
+            },
+            None | Some(DefineKind::Text) => (Some(String::from(value)), "text"),
+            Some(DefineKind::Int) => (value.parse::<i64>().ok().map(|n| n.to_string()), "an integer"),
+            Some(DefineKind::Bool) => (value.parse::<bool>().ok().map(|b| b.to_string()), "a boolean"),
+        };
+        let value = normalized.ok_or_else(|| {
+            ParseError::Value { name: arg.name.clone(), value: pair.clone(), kind: ValueErrorKind::Type(expected) }
+        })?;
+
+        defines.entry(String::from(arg.key()))
+            .or_default()
+            .insert(String::from(key), value);
+        Ok(())
+    }
+
+    fn is_option(&self, parser_args: &[Arg], token: &str) -> bool {
+        if find_prefixed(parser_args, token).is_some() {
+            return true;
+        } else if token.starts_with("+") {
+            return self.plus_flags.get();
+        } else if !token.starts_with("-") {
+            return false;
+        }
+
+        // Negative numbers are only options if they are registered as one
+        !(self.allow_negative_numbers.get()
+            && is_negative_number(token)
+            && self.find_option(parser_args, token).is_none()
+            && (!self.plus_flags.get() || self.find_toggle(parser_args, token).is_none()))
+    }
+
+    fn find_toggle<'a>(&self, parser_args: &'a [Arg], token: &str) -> Option<&'a Arg> {
+        if token.starts_with("--") {
+            return None;
+        }
+        let rest = token.strip_prefix('+').or_else(|| token.strip_prefix('-'))?;
+        let mut chars = rest.chars();
+        let short = match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        };
+
+        parser_args.iter()
+            .filter(|arg| !arg.expecting && !matches!(arg.arg_type, ArgTypes::Param(_)))
+            .find(|arg| {
+                (!rest.is_empty() && (arg.name == rest || arg.aliases.iter().any(|a| a == rest)))
+                    || (arg.short.is_some() && arg.short == short)
+            })
+    }
+
+    fn find_option<'a>(&self, parser_args: &'a [Arg], token: &str) -> Option<&'a Arg> {
+        if let Some((arg, _)) = find_prefixed(parser_args, token) {
+            Some(arg)
+        } else if let Some(rest) = token.strip_prefix("--") {
+            // The name ends at an attached value, and `--=value` has no name at all
+            let name = rest.split('=').next().filter(|name| !name.is_empty())?;
+            parser_args.iter()
+                .filter(|arg| arg.prefix.is_none())
+                .find(|arg| arg.name == name || arg.aliases.iter().any(|a| a == name))
+        } else {
+            let (short, _) = split_short(token)?;
+            parser_args.iter().find(|arg| arg.short == Some(short))
+        }
+    }
+
+    /// Returns the value attached to the option `token` with `=`, taking [`Arg::prefix()`] into account.
+    fn attached<'t>(&self, parser_args: &[Arg], token: &'t str) -> Option<&'t str> {
+        match find_prefixed(parser_args, token) {
+            Some((_, value)) => value,
+            None => attached_value(token),
+        }
+    }
+
+    /// Returns the index of the param named by a positional like `file=a.txt`, with its value, if [`Parser::param_equals_as_value()`] is enabled.
+    fn named_param(&self, parser_args: &[Arg], token: &str) -> Option<(usize, String)> {
+        if !self.param_equals.get() {
+            return None;
+        }
+        let (name, value) = token.split_once('=')?;
+        let index = parser_args.iter().position(|arg| matches!(arg.arg_type, ArgTypes::Param(_)) && arg.name == name)?;
+        Some((index, String::from(value)))
+    }
+
+    /// Returns `true` if `token` is an unknown long option collected by [`Parser::catch_all()`].
+    fn catches(&self, parser_args: &[Arg], token: &str) -> bool {
+        self.catch_all.borrow().is_some()
+            && token.len() > 2
+            && token.starts_with("--")
+            && self.find_option(parser_args, token).is_none()
+    }
+
+    /// Finds inputs which took a value that could have filled a missing param, like `--out a.txt b.txt` with two params.
+    /// The value must be a separate token directly followed by positionals, with no other options in between.
+    fn find_warnings(&self, parser_args: &[Arg], tokens: &[String]) -> Vec<ParseWarning> {
+        let mut reader = Reader::new(self, parser_args);
+        let readings: Vec<Reading> = tokens.iter().map(|token| reader.read(token, false)).collect();
+        let expected = parser_args.iter()
+            .filter(|arg| matches!(arg.arg_type, ArgTypes::Param(_)))
+            .count();
+        if reader.positionals >= expected {
+            return vec![];
+        }
+
+        let mut warnings = vec![];
+        let mut last_value: Option<(&Arg, &String)> = None;
+
+        for (token, reading) in tokens.iter().zip(readings) {
+            match reading {
+                Reading::Terminator => break,
+                // An input only takes a single token, so this is always the one right after it
+                Reading::Value(arg) if arg.expecting && arg.terminator.is_none() => last_value = Some((arg, token)),
+                Reading::Positional(_) | Reading::Extra => {
+                    if let Some((arg, value)) = last_value.take() {
+                        warnings.push(ParseWarning::ValueMayBePositional { arg: arg.name.clone(), value: value.clone() });
+                    }
+                },
+                _ => last_value = None,
+            }
+        }
+
+        warnings
+    }
+
+    /// Parses through the remaining arguments and returns a hashmap of arguments passed and their relevant values.
+    /// 
+    /// # Example
+    /// ```no_run
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// let args = vec![
+    ///     Arg::new().param("p1"),
+    ///     Arg::new().param("p2"),
+    ///     Arg::new().flag("help").short('h'),
+    /// ];
+    /// parser.add_args(args);
+    /// 
+    /// let mut input_args = std::env::args();
+    /// input_args.next();
+    /// 
+    /// let hashmap = parser.parse(&mut input_args).unwrap();
+    /// println!("p1: {:?}, p2: {:?}", hashmap.get("p1"), hashmap.get("p2"));
+    /// if hashmap.contains_key("help") {
+    ///     println!("Help requested!");
+    /// }
+    /// ```
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn parse(&self, args: &mut impl Iterator<Item = String>) -> Result<HashMap<String, Option<String>>, Box<dyn Error>> {
+        Ok(self.parse_tokens(self.collect_tokens(args)?)?.into_map())
+    }
+
+    /// Returns the value of the argument in a hashmap returned by [`Parser::parse()`], like [`ParseOutput::value_of()`].
+    /// Returns `None` if it was not provided or doesn't take a value.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("out"), Arg::new().flag("verbose")]);
+    /// 
+    /// let hashmap = parser.parse(&mut ["--out", "a.txt", "--verbose"].map(String::from).into_iter()).unwrap();
+    /// assert_eq!(parser.value_of(&hashmap, "out"), Some("a.txt"));
+    /// assert_eq!(parser.value_of(&hashmap, "verbose"), None);
+    /// ```
+    pub fn value_of<'a>(&self, map: &'a HashMap<String, Option<String>>, name: &str) -> Option<&'a str> {
+        map.get(name)?.as_deref()
+    }
+
+    /// Returns `true` if the argument is in a hashmap returned by [`Parser::parse()`], like [`ParseOutput::is_present()`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("verbose"), Arg::new().flag("quiet")]);
+    /// 
+    /// let hashmap = parser.parse(&mut ["--verbose"].map(String::from).into_iter()).unwrap();
+    /// assert!(parser.is_present(&hashmap, "verbose"));
+    /// assert!(!parser.is_present(&hashmap, "quiet"));
+    /// ```
+    pub fn is_present(&self, map: &HashMap<String, Option<String>>, name: &str) -> bool {
+        map.contains_key(name)
+    }
+
+    /// Parses from `args` like [`Parser::parse()`], stopping before the first positional left once every param is filled
+    /// so the caller can inspect what's left. A `--` also stops parsing and is consumed.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("verbose").short('v'), Arg::new().input("out").short('o')]);
+    /// 
+    /// let mut args = ["-v", "-o", "a.txt", "build", "--release"].map(String::from).into_iter().peekable();
+    /// let hashmap = parser.parse_peekable(&mut args).unwrap();
+    /// assert!(hashmap.contains_key("verbose"));
+    /// assert_eq!(args.peek().map(String::as_str), Some("build"));
+    /// ```
+    /// Each token is read as it's peeked, so nothing past the stop is consumed,
+    /// and going over [`Parser::max_args()`] leaves the rest of `args` untouched.
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn parse_peekable<I>(&self, args: &mut Peekable<I>) -> Result<HashMap<String, Option<String>>, Box<dyn Error>>
+    where
+        I: Iterator<Item = String>,
+    {
+        let parser_args = self.args();
+        let mut reader = Reader::new(self, &parser_args);
+        let mut tokens: Vec<String> = vec![];
+
+        while let Some(next) = args.peek() {
+            match reader.read(next, false) {
+                Reading::Terminator => {
+                    args.next();
+                    break;
+                },
+                Reading::Extra => break,
+                _ => {
+                    // The token past the limit is only peeked, so it's left in `args`
+                    if let Some(limit) = self.max_args.get().filter(|limit| tokens.len() >= *limit) {
+                        return Err(ParseError::TooManyArgs { limit }.into());
+                    }
+                    tokens.extend(args.next());
+                },
+            }
+        }
+
+        Ok(self.parse_tokens(tokens)?.into_map())
+    }
+
+    /// Parses the arguments like [`Parser::parse()`], then builds a `T` out of them with [`FromMatches`].
+    /// 
+    /// # Example
+    /// See [`FromMatches`].
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn parse_into_struct<T: FromMatches>(&self, args: &mut impl Iterator<Item = String>) -> Result<T, ParseError> {
+        T::from_matches(&self.parse_tokens(self.collect_tokens(args)?)?)
+    }
+
+    /// Same as [`Parser::parse()`], but takes the first argument as the program name instead of parsing it.
+    /// This means [`std::env::args()`] can be passed in directly.
+    /// 
+    /// # Example
+    /// ```no_run
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("help").short('h'));
+    /// 
+    /// let output = parser.parse_with_program_name(&mut std::env::args()).unwrap();
+    /// println!("Running {:?}", output.bin_name());
+    /// ```
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn parse_with_program_name(&self, args: &mut impl Iterator<Item = String>) -> Result<ParseOutput, ParseError> {
+        let bin_name = args.next();
+        let mut output = self.parse_tokens(self.collect_tokens(args)?)?;
+        output.bin_name = bin_name;
+        Ok(output)
+    }
+
+    /// Parses every item of `itr` and returns the arguments found, mirroring the naming of other parser crates.
+    /// Unlike [`Parser::parse()`], the items can be anything convertible into a `String`.
+    /// Note the first item is not skipped, so the program name must be removed by the caller.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().param("file"), Arg::new().flag("verbose").short('v')]);
+    /// 
+    /// let output = parser.try_get_matches_from(["-v", "notes.txt"]).unwrap();
+    /// assert_eq!(output.value_of("file"), Some("notes.txt"));
+    /// assert!(parser.try_get_matches_from(["notes.txt", "-x"]).is_err());
+    /// ```
+    /// No input makes parsing panic, so it can be used where panicking isn't allowed, and every problem is returned as a [`ParseError`].
+    /// Callbacks like [`Parser::on_token()`] can use the parser again while it's parsing,
+    /// but closures passed to the parser, like [`Arg::map_value()`], can still panic on their own.
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn try_get_matches_from<I, T>(&self, itr: I) -> Result<ParseOutput, ParseError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.parse_tokens(self.collect_tokens(itr.into_iter().map(Into::into))?)
+    }
+
+    /// Parses a borrowed slice of arguments like [`Parser::try_get_matches_from()`], leaving the slice untouched.
+    /// This is useful when the arguments are already in a `Vec`, so they can be inspected or parsed again afterwards.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().param("file"));
+    /// 
+    /// let args = vec![String::from("notes.txt")];
+    /// let output = parser.parse_slice(&args).unwrap();
+    /// assert_eq!(output.value_of("file"), Some(args[0].as_str()));
+    /// ```
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn parse_slice(&self, args: &[String]) -> Result<ParseOutput, ParseError> {
+        self.parse_tokens(args.to_vec())
+    }
+
+    /// Same as [`Parser::try_get_matches_from()`], but panics with the formatted error if parsing fails.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().param("file"));
+    /// 
+    /// let output = parser.get_matches_from(vec![String::from("notes.txt")]);
+    /// assert!(output.is_present("file"));
+    /// ```
+    #[must_use]
+    pub fn get_matches_from<I, T>(&self, itr: I) -> ParseOutput
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        match self.try_get_matches_from(itr) {
+            Ok(output) => output,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Parses like [`Parser::try_get_matches_from()`], returning each value in the order it was given rather than as a map.
+    /// This keeps the order of repeated options, like layered overrides where a later `--set` replaces an earlier one.
+    /// Options ignored by [`DuplicatePolicy::FirstWins`] are left out, and defaults aren't included since they were never given.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, DuplicatePolicy, Parser, Value};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("set"), Arg::new().flag("verbose").short('v')]);
+    /// parser.on_duplicate(DuplicatePolicy::LastWins);
+    /// 
+    /// let ordered = parser.parse_ordered(["--set", "a", "-v", "--set", "b"]).unwrap();
+    /// assert_eq!(ordered, [
+    ///     (String::from("set"), Value::Str(String::from("a"))),
+    ///     (String::from("verbose"), Value::Flag),
+    ///     (String::from("set"), Value::Str(String::from("b"))),
+    /// ]);
+    /// ```
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn parse_ordered<I, T>(&self, itr: I) -> Result<Vec<(String, Value)>, ParseError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let tokens = self.collect_tokens(itr.into_iter().map(Into::into))?;
+        let mut order = vec![];
+        self.parse_recorded(tokens, vec![], Some(&mut order), None)?;
+        Ok(order)
+    }
+
+    /// Parses options up to the first positional, returning the rest of the tokens untouched.
+    /// This splits a wrapper's own options from a command it runs, like `-v -o out run --child-flag`.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().flag("verbose").short('v'), Arg::new().input("out").short('o')]);
+    /// 
+    /// let (output, rest) = parser.parse_until_positional(["-v", "-o", "out", "run", "--child-flag"]).unwrap();
+    /// assert!(output.is_present("verbose"));
+    /// assert_eq!(output.value_of("out"), Some("out"));
+    /// assert_eq!(rest, ["run", "--child-flag"]);
+    /// ```
+    /// A `--` also ends the options and is dropped, so everything after it is returned.
+    /// Params are left unfilled, since the first positional is returned with the rest, so they aren't required.
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn parse_until_positional<I, T>(&self, itr: I) -> Result<(ParseOutput, Vec<String>), ParseError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let tokens = self.collect_tokens(itr.into_iter().map(Into::into))?;
+        let mut rest = vec![];
+        let output = self.parse_recorded(tokens, vec![], None, Some(&mut rest))?;
+        Ok((output, rest))
+    }
+
+    /// Parses like [`Parser::try_get_matches_from()`], also collecting advisory [`ParseWarning`]s about confusing input.
+    /// Warnings never cause parsing to fail, and are available through [`ParseOutput::warnings()`].
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, ParseWarning, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("out"), Arg::new().param("src"), Arg::new().param("dest")]);
+    /// parser.require_params(false);
+    /// 
+    /// let output = parser.parse_with_warnings(["--out", "a.txt", "b.txt"]).unwrap();
+    /// assert_eq!(output.warnings(), [ParseWarning::ValueMayBePositional {
+    ///     arg: String::from("out"),
+    ///     value: String::from("a.txt"),
+    /// }]);
+    /// ```
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn parse_with_warnings<I, T>(&self, itr: I) -> Result<ParseOutput, ParseError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let tokens = self.collect_tokens(itr.into_iter().map(Into::into))?;
+        let warnings = self.find_warnings(&self.args.borrow(), &tokens);
+        let mut output = self.parse_tokens(tokens)?;
+        output.warnings = warnings;
+        Ok(output)
+    }
+
+    /// Splits `line` into tokens like a shell would and parses them like [`Parser::try_get_matches_from()`].
+    /// Tokens are separated by whitespace, `'` and `"` quote a value, and `\` escapes the next char.
+    /// A quoted `"--"` is a literal value rather than the options terminator.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("msg").short('m'), Arg::new().param("file")]);
+    /// 
+    /// let output = parser.parse_str(r#"-m "hello world" notes.txt"#).unwrap();
+    /// assert_eq!(output.value_of("msg"), Some("hello world"));
+    /// assert_eq!(output.value_of("file"), Some("notes.txt"));
+    /// ```
+    /// An unterminated quote returns [`ParseError::Unexpected`].
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn parse_str(&self, line: &str) -> Result<ParseOutput, ParseError> {
+        let (tokens, quoted) = tokenize(line)?.into_iter().unzip();
+        self.parse_quoted(tokens, quoted)
+    }
+
+    /// Reads a command line from standard input and parses it like [`Parser::parse_str()`], e.g. for `tool -` in a pipeline.
+    /// 
+    /// # Example
+    /// ```no_run
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_arg(Arg::new().flag("verbose"));
+    /// 
+    /// let args: Vec<String> = std::env::args().skip(1).collect();
+    /// let output = if args == ["-"] {
+    ///     parser.parse_stdin().unwrap()
+    /// } else {
+    ///     parser.try_get_matches_from(args).unwrap()
+    /// };
+    /// ```
+    /// Use [`Parser::parse_reader()`] to read from somewhere else, like a fake input in tests.
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn parse_stdin(&self) -> Result<ParseOutput, Box<dyn Error>> {
+        self.parse_reader(std::io::stdin().lock())
+    }
+
+    /// Reads everything from `reader` as a command line and parses it like [`Parser::parse_str()`].
+    /// Tokens can be split across lines, since newlines are whitespace like any other.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().input("msg").short('m'), Arg::new().param("file")]);
+    /// 
+    /// let output = parser.parse_reader("-m 'hello world'\nnotes.txt\n".as_bytes()).unwrap();
+    /// assert_eq!(output.value_of("msg"), Some("hello world"));
+    /// assert_eq!(output.value_of("file"), Some("notes.txt"));
+    /// ```
+    /// Errors reading the input are returned as they are, as well as [`ParseError`]s.
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn parse_reader(&self, mut reader: impl Read) -> Result<ParseOutput, Box<dyn Error>> {
+        let mut line = String::new();
+        reader.read_to_string(&mut line)?;
+        Ok(self.parse_str(&line)?)
+    }
+
+    /// Parses arguments which are already split into names and values, like the fields of an HTTP or RPC request.
+    /// Values are checked and defaults and requirements applied just like when parsing tokens.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// # use std::collections::HashMap;
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().param("file"), Arg::new().input("timeout").duration(), Arg::new().flag("verbose")]);
+    /// 
+    /// let output = parser.parse_map(&HashMap::from([
+    ///     (String::from("file"), String::from("notes.txt")),
+    ///     (String::from("timeout"), String::from("2s")),
+    ///     (String::from("verbose"), String::from("true")),
+    /// ])).unwrap();
+    /// assert_eq!(output.value_of("file"), Some("notes.txt"));
+    /// assert_eq!(output.value_of("timeout"), Some("2000"));
+    /// assert!(output.is_present("verbose"));
+    /// ```
+    /// Flags take `"true"` or `"false"`, and an [`Arg::takes_positionals()`] flag takes its group separated by whitespace.
+    /// An unknown name returns [`ParseError::Unexpected`], and missing params are reported by [`ParseError::MissingRequired`].
+    #[must_use = "the parse result should be checked for errors"]
+    pub fn parse_map(&self, map: &HashMap<String, String>) -> Result<ParseOutput, ParseError> {
+        if let Some(limit) = self.max_args.get().filter(|limit| map.len() > *limit) {
+            return Err(ParseError::TooManyArgs { limit });
+        }
+
+        let mut hashmap: HashMap<String, Option<String>> = HashMap::new();
+        let mut lists: HashMap<String, Vec<String>> = HashMap::new();
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        let mut defines: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut parser_args = self.args();
+
+        let mut names: Vec<&String> = map.keys().collect();
+        names.sort();
+        for name in names {
+            let value = map[name].clone();
+            let Some(arg) = parser_args.iter_mut().find(|arg| arg.key() == name) else {
+                return Err(ParseError::Unexpected(name.clone()));
+            };
+
+            if let ArgTypes::Param(_) = arg.arg_type {
+                arg.set_used(true);
+            }
+
+            if arg.terminator.is_some() {
+                lists.insert(name.clone(), vec![arg.process_value(value)?]);
+            } else if arg.defines {
+                self.insert_define(&mut defines, arg, value)?;
+            } else if arg.delimiter.is_some() {
+                lists.insert(name.clone(), arg.split_values(value)?);
+                hashmap.insert(name.clone(), None);
+            } else if arg.expecting || matches!(arg.arg_type, ArgTypes::Param(_)) {
+                hashmap.insert(name.clone(), Some(arg.process_value(value)?));
+            } else if arg.max_positionals > 0 {
+                let group: Vec<String> = value.split_whitespace().map(String::from).collect();
+                if group.len() < arg.positionals || group.len() > arg.max_positionals {
+                    return Err(ParseError::MissingValue(arg.name.clone()));
+                }
+                let group = group.into_iter().map(|value| arg.process_value(value)).collect::<Result<_, _>>()?;
+                lists.insert(name.clone(), group);
+                hashmap.insert(name.clone(), None);
+            } else {
+                let Ok(present) = value.parse::<bool>() else {
+                    return Err(ParseError::Value { name: arg.name.clone(), value, kind: ValueErrorKind::Type("a boolean") });
+                };
+                if present {
+                    if arg.countable {
+                        counts.insert(name.clone(), 1);
+                    }
+                    hashmap.insert(name.clone(), None);
+                }
+            }
+        }
+
+        let missing: Vec<String> = parser_args.iter()
+            .filter(|arg| matches!(arg.arg_type, ArgTypes::Param(false)))
+            .map(|arg| arg.name.clone())
+            .collect();
+        if !missing.is_empty() && self.require_params.get() && !self.validation_disabled(&parser_args, &hashmap) {
+            return Err(ParseError::MissingRequired(missing));
+        }
+
+        self.finish(parser_args, hashmap, lists, counts, defines, self.require_params.get())
+    }
+
+    /// Collects the tokens to parse, stopping as soon as there are more than [`Parser::max_args()`] allows.
+    fn collect_tokens(&self, tokens: impl Iterator<Item = String>) -> Result<Vec<String>, ParseError> {
+        let Some(limit) = self.max_args.get() else {
+            return Ok(tokens.collect());
+        };
+
+        let tokens: Vec<String> = tokens.take(limit.saturating_add(1)).collect();
+        if tokens.len() > limit {
+            return Err(ParseError::TooManyArgs { limit });
+        }
+        Ok(tokens)
+    }
+
+    fn parse_tokens(&self, tokens: Vec<String>) -> Result<ParseOutput, ParseError> {
+        self.parse_quoted(tokens, vec![])
+    }
+
+    /// Parses `tokens`, where `quoted` marks the tokens that were quoted in the original command line.
+    /// Tokens past the end of `quoted` are unquoted.
+    fn parse_quoted(&self, tokens: Vec<String>, quoted: Vec<bool>) -> Result<ParseOutput, ParseError> {
+        self.parse_recorded(tokens, quoted, None, None)
+    }
+
+    /// Like [`Parser::parse_quoted()`], also pushing the values onto `order` in the order they were given when it's set,
+    /// for [`Parser::parse_ordered()`].
+    /// When `rest` is set, parsing stops at the first positional or `--` and the tokens from there on are moved into it,
+    /// dropping the `--`, for [`Parser::parse_until_positional()`].
+    fn parse_recorded(
+        &self,
+        mut tokens: Vec<String>,
+        quoted: Vec<bool>,
+        mut order: Option<&mut Vec<(String, Value)>>,
+        rest: Option<&mut Vec<String>>,
+    ) -> Result<ParseOutput, ParseError> {
+        if let Some(limit) = self.max_args.get().filter(|limit| tokens.len() > *limit) {
+            return Err(ParseError::TooManyArgs { limit });
+        }
+
+        let mut hashmap: HashMap<String, Option<String>> = HashMap::new();
+        let mut lists: HashMap<String, Vec<String>> = HashMap::new();
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        let mut defines: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut stats = ParseStats { tokens: tokens.len(), ..ParseStats::default() };
+        let mut forms: HashMap<String, MatchedForm> = HashMap::new();
+        let mut parser_args = self.args.clone().take();
+        let mut seen_positional = false;
+        let trailing = self.trailing.borrow().clone();
+        let catch_all = self.catch_all.borrow().clone();
+
+        let mut shorts = vec![];
+        for short in parser_args.iter().filter_map(|arg| arg.short) {
+            if shorts.contains(&short) {
+                return Err(ParseError::AmbiguousShort(short));
+            }
+            shorts.push(short);
+        }
+
+        // The mode is checked first, since the rest can't be read without it
+        let modes = self.modes.borrow().clone();
+        if !modes.is_empty() {
+            match tokens.first() {
+                None => return Err(ParseError::MissingRequired(vec![String::from("mode")])),
+                Some(mode) if !modes.contains(mode) => {
+                    return Err(ParseError::InvalidMode { got: mode.clone(), allowed: modes });
+                },
+                Some(_) => {},
+            }
+        }
+
+        // Params aren't filled when parsing stops at the first positional, so they can't be required
+        let require_params = self.require_params.get() && rest.is_none();
+        let has_params = parser_args.iter().any(|arg| matches!(arg.arg_type, ArgTypes::Param(_)));
+        if tokens.len() == usize::from(!modes.is_empty()) && require_params && has_params {
+            // Running the command with nothing at all gets an error naming every param along with the options
+            // still missing once defaults, seeds and the environment are applied
+            let missing = match self.finish(parser_args.clone(), HashMap::new(), HashMap::new(), HashMap::new(), HashMap::new(), false) {
+                Err(ParseError::MissingRequired(missing)) => missing,
+                _ => vec![],
+            };
+            let missing = parser_args.iter()
+                .filter(|arg| matches!(arg.arg_type, ArgTypes::Param(_)) || missing.contains(&arg.name))
+                .map(|arg| arg.name.clone())
+                .collect();
+            return Err(ParseError::MissingRequired(missing));
+        }
+
+        let mut reader = Reader::new(self, &parser_args);
+        reader.until_positional = rest.is_some();
+        let readings: Vec<Reading> = tokens.iter()
+            .enumerate()
+            .map(|(i, token)| reader.read(token, quoted.get(i).copied().unwrap_or(false)))
+            .take_while(|reading| !matches!(reading, Reading::Rest))
+            .collect();
+        if let Some(rest) = rest {
+            *rest = tokens.split_off(readings.len());
+            if rest.first().is_some_and(|token| token == "--") {
+                rest.remove(0);
+            }
+        }
+
+        // Check the number of positionals up front to give a clearer error than failing midway through
+        let expected = parser_args.iter()
+            .filter(|arg| matches!(arg.arg_type, ArgTypes::Param(_)))
+            .count();
+        let got = reader.positionals;
+        let forced = tokens.iter().zip(&readings).any(|(token, reading)| match reading {
+            Reading::Option(arg) => arg.disables_validation,
+            Reading::Toggle(arg) => arg.disables_validation && token.starts_with('+'),
+            Reading::Cluster => split_cluster(&parser_args, token)
+                .is_some_and(|cluster| cluster.iter().any(|(arg, _)| arg.disables_validation)),
+            _ => false,
+        });
+        if got > expected {
+            return Err(ParseError::TooManyPositionals { expected, got });
+        } else if got < expected && require_params && !forced {
+            // An input left without its value is the likelier mistake, so it's reported instead
+            if let Some(arg) = reader.dangling() {
+                return Err(ParseError::MissingValue(arg.name.clone()));
+            }
+            return Err(ParseError::TooFewPositionals { expected, got });
+        }
+
+        let filled = std::mem::take(&mut reader.filled);
+        let is_value = |(_, reading): &(String, Reading)| matches!(reading, Reading::Value(_));
+        // Each token is reported as the loop takes it, and a parse started by the callback itself isn't reported
+        let observer = self.on_token.borrow().clone();
+        let mut tokens = tokens.into_iter()
+            .zip(readings)
+            .inspect(|(token, reading)| {
+                if let Some(mut callback) = observer.as_ref().and_then(|observer| observer.0.try_borrow_mut().ok()) {
+                    callback(token, reader.classify(token, *reading));
+                }
+            })
+            .peekable();
+
+        while let Some((token, reading)) = tokens.next() {
+            let option = matches!(reading, Reading::Toggle(_) | Reading::Option(_) | Reading::Cluster | Reading::Caught | Reading::Unknown);
+            if option && seen_positional && self.strict_ordering.get() {
+                return Err(ParseError::OptionAfterPositional(token));
+            }
+
+            match reading {
+                Reading::Mode => {
+                    record(&mut order, "mode", || Value::Str(token.clone()));
+                    hashmap.insert(String::from("mode"), Some(token));
+                },
+                Reading::Terminator => {
+                    // Stop parsing options, and collect the rest of the tokens if a trailing arg was set
+                    if let Some(name) = &trailing {
+                        let rest: Vec<String> = tokens.by_ref().map(|(token, _)| token).collect();
+                        record(&mut order, name, || Value::Multi(rest.clone()));
+                        lists.insert(name.clone(), rest);
+                    }
+                },
+                Reading::Toggle(arg) => {
+                    // Toggled flag
+                    stats.options += 1;
+                    forms.insert(String::from(arg.key()), matched_form(arg, &token));
+                    let enabled = token.starts_with("+");
+                    if self.insert_value(&mut hashmap, String::from(arg.key()), token, |_| Ok(Some(enabled.to_string())))? {
+                        record(&mut order, arg.key(), || Value::Str(enabled.to_string()));
+                    }
+                },
+                Reading::Caught => {
+                    // Unknown long option
+                    let Some(name) = &catch_all else {
+                        return Err(ParseError::Unexpected(token));
+                    };
+                    let (key, value) = match token[2..].split_once('=') {
+                        Some((key, value)) => (String::from(key), String::from(value)),
+                        None => {
+                            let value = tokens.next_if(|(_, reading)| matches!(reading, Reading::CaughtValue))
+                                .map_or_else(|| String::from("true"), |(value, _)| value);
+                            (String::from(&token[2..]), value)
+                        },
+                    };
+                    record(&mut order, &key, || Value::Str(value.clone()));
+                    defines.entry(name.clone()).or_default().insert(key, value);
+                    stats.unknowns += 1;
+                },
+                Reading::Option(_) | Reading::Cluster => {
+                    // Full or short arg
+                    let (arg, attached) = match reading {
+                        Reading::Option(arg) => {
+                            forms.insert(String::from(arg.key()), matched_form(arg, &token));
+                            (arg, self.attached(&parser_args, &token).map(String::from))
+                        },
+                        _ => {
+                            // Cluster of shorts, where every arg but the last is a flag
+                            let mut cluster = split_cluster(&parser_args, &token).unwrap_or_default();
+                            let Some((last, value)) = cluster.pop() else {
+                                return Err(ParseError::Unexpected(token));
+                            };
+                            stats.options += cluster.len();
+                            for arg in cluster.iter().map(|(arg, _)| arg).chain([&last]) {
+                                if let Some(short) = arg.short {
+                                    forms.insert(String::from(arg.key()), MatchedForm::Short(short));
+                                }
+                            }
+                            for (arg, _) in cluster {
+                                if arg.countable {
+                                    *counts.entry(String::from(arg.key())).or_insert(0) += 1;
+                                    hashmap.insert(String::from(arg.key()), None);
+                                } else if !self.insert_value(&mut hashmap, String::from(arg.key()), token.clone(), |_| Ok(None))? {
+                                    continue;
+                                }
+                                record(&mut order, arg.key(), || Value::Flag);
+                            }
+                            (last, value.map(String::from))
+                        },
+                    };
+                    stats.options += 1;
+
+                    if arg.terminator.is_some() {
+                        // Take every token up to the unquoted terminator, which is dropped
+                        let mut list = vec![];
+                        if let Some(value) = attached {
+                            list.push(arg.process_value(value)?);
+                        }
+                        while let Some((value, _)) = tokens.next_if(is_value) {
+                            list.push(arg.process_value(value)?);
+                        }
+                        tokens.next_if(|(_, reading)| matches!(reading, Reading::End));
+                        if self.insert_list(&mut hashmap, &mut lists, arg, token, list)? {
+                            record(&mut order, arg.key(), || Value::Multi(lists[arg.key()].clone()));
+                        }
+                    } else if arg.max_positionals > 0 && attached.is_none() {
+                        // Take the group of positionals following the flag
+                        let mut group = vec![];
+                        while let Some((value, _)) = tokens.next_if(is_value) {
+                            group.push(arg.process_value(value)?);
+                        }
+                        if group.len() < arg.positionals {
+                            return Err(ParseError::MissingValue(arg.name.clone()));
+                        }
+
+                        if self.insert_list(&mut hashmap, &mut lists, arg, token, group)? {
+                            record(&mut order, arg.key(), || Value::Multi(lists[arg.key()].clone()));
+                        }
+                    } else if let Some(value) = attached {
+                        // Arg with its value attached
+                        if !arg.expecting {
+                            return Err(ParseError::Unexpected(token));
+                        }
+                        self.insert_given(&mut hashmap, &mut lists, &mut defines, &mut order, arg, token, Some(value))?;
+                    } else if arg.expecting {
+                        // Input taking the next token, which can't be another option or the terminator
+                        let Some((value, _)) = tokens.next_if(is_value) else {
+                            return Err(match tokens.next() {
+                                Some((next, _)) if next != "--" => ParseError::Unexpected(next),
+                                _ => ParseError::MissingValue(arg.name.clone()),
+                            });
+                        };
+                        self.insert_given(&mut hashmap, &mut lists, &mut defines, &mut order, arg, value, None)?;
+                    } else if arg.countable {
+                        *counts.entry(String::from(arg.key())).or_insert(0) += 1;
+                        hashmap.insert(String::from(arg.key()), None);
+                        record(&mut order, arg.key(), || Value::Flag);
+                    } else if self.insert_value(&mut hashmap, String::from(arg.key()), token, |_| Ok(None))? {
+                        record(&mut order, arg.key(), || Value::Flag);
+                    }
+                },
+                Reading::Positional(index) => {
+                    // params, either named or the next one not filled yet
+                    let arg = &parser_args[index];
+                    let value = match self.named_param(&parser_args, &token) {
+                        Some((_, value)) => value,
+                        None => token.clone(),
+                    };
+
+                    if self.insert_value(&mut hashmap, String::from(arg.key()), token, |_| arg.process_value(value).map(Some))? {
+                        record(&mut order, arg.key(), || Value::Str(hashmap[arg.key()].clone().unwrap_or_default()));
+                    }
+                    stats.positionals += 1;
+                    seen_positional = true;
+                },
+                Reading::Value(_) | Reading::End | Reading::CaughtValue | Reading::Extra | Reading::Trailing | Reading::Unknown | Reading::Rest => {
+                    return Err(ParseError::Unexpected(token));
+                },
+            }
+        }
+
+        for index in filled {
+            parser_args[index].set_used(true);
+        }
+
+        let output = self.finish(parser_args, hashmap, lists, counts, defines, require_params)?;
+        Ok(ParseOutput { stats, forms, ..output })
+    }
+
+    /// Stores the value given to an input in the way the input takes it.
+    /// The value is either `attached` to the `token` naming the input, or the `token` itself when it came as the next token.
+    #[allow(clippy::too_many_arguments)]
+    fn insert_given(
+        &self,
+        hashmap: &mut HashMap<String, Option<String>>,
+        lists: &mut HashMap<String, Vec<String>>,
+        defines: &mut HashMap<String, HashMap<String, String>>,
+        order: &mut Option<&mut Vec<(String, Value)>>,
+        arg: &Arg,
+        token: String,
+        attached: Option<String>,
+    ) -> Result<(), ParseError> {
+        if arg.defines {
+            let value = attached.unwrap_or(token);
+            record(order, arg.key(), || Value::Str(value.clone()));
+            self.insert_define(defines, arg, value)?;
+        } else if arg.delimiter.is_some() {
+            let values = arg.split_values(attached.unwrap_or_else(|| token.clone()))?;
+            if self.insert_list(hashmap, lists, arg, token, values)? {
+                record(order, arg.key(), || Value::Multi(lists[arg.key()].clone()));
+            }
+        } else if self.insert_value(hashmap, String::from(arg.key()), token, |token| arg.process_value(attached.unwrap_or(token)).map(Some))? {
+            record(order, arg.key(), || Value::Str(hashmap[arg.key()].clone().unwrap_or_default()));
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if an argument set with [`Arg::disables_validation()`] was provided and not disabled with `-name`.
+    fn validation_disabled(&self, parser_args: &[Arg], hashmap: &HashMap<String, Option<String>>) -> bool {
+        parser_args.iter()
+            .filter(|arg| arg.disables_validation)
+            .any(|arg| hashmap.get(arg.key()).is_some_and(|value| value.as_deref() != Some("false")))
+    }
+
+    /// Applies implications, requirements and defaults to the arguments found, shared by every way of parsing.
+    /// Unfilled params are only an error if `require_params` is set.
+    fn finish(
+        &self,
+        parser_args: Vec<Arg>,
+        mut hashmap: HashMap<String, Option<String>>,
+        lists: HashMap<String, Vec<String>>,
+        counts: HashMap<String, u32>,
+        defines: HashMap<String, HashMap<String, String>>,
+        require_params: bool,
+    ) -> Result<ParseOutput, ParseError> {
+        // Flags not given on the command line can still be enabled by the environment
+        for arg in &parser_args {
+            let Some(var) = arg.env_flag.as_ref().filter(|_| !hashmap.contains_key(arg.key())) else {
+                continue;
+            };
+            let source = self.env_source.borrow().clone();
+            let value = match source {
+                Some(source) => (source.0)(var),
+                None => std::env::var(var).ok(),
+            };
+            if value.is_some_and(|value| ["1", "true", "yes"].contains(&value.to_lowercase().as_str())) {
+                hashmap.insert(String::from(arg.key()), None);
+            }
+        }
+
+        // Add implied args, each name is only added once so cycles end on their own
+        let mut pending: Vec<String> = hashmap.iter()
+            .filter(|(_, value)| value.as_deref() != Some("false"))
+            .map(|(name, _)| name.clone())
+            .collect();
+        while let Some(name) = pending.pop() {
+            if let Some(arg) = parser_args.iter().find(|arg| arg.key() == name) {
+                for implied in &arg.implies {
+                    if !hashmap.contains_key(implied) {
+                        hashmap.insert(implied.clone(), None);
+                        pending.push(implied.clone());
+                    }
+                }
+            }
+        }
+
+        let forced = self.validation_disabled(&parser_args, &hashmap);
+        let provided = |key: &str| hashmap.contains_key(key) || lists.contains_key(key) || defines.contains_key(key);
+        if let Some(arg) = parser_args.iter().filter(|_| !forced).find(|arg| {
+            !arg.requires_any.is_empty() && provided(arg.key()) && !arg.requires_any.iter().any(|other| provided(other))
+        }) {
+            return Err(ParseError::MissingRequirement { arg: arg.name.clone(), requires: arg.requires_any.clone() });
+        }
+
+        for (members, required) in self.groups.borrow().iter().filter(|_| !forced) {
+            let reason = match members.iter().filter(|member| provided(member)).count() {
+                0 if *required => GroupViolationKind::NoneSelected,
+                0 | 1 => continue,
+                _ => GroupViolationKind::TooMany,
+            };
+            return Err(ParseError::GroupViolation { members: members.clone(), reason });
+        }
+
+        for group in self.together.borrow().iter().filter(|_| !forced) {
+            let (present, missing): (Vec<String>, Vec<String>) = group.iter().cloned().partition(|member| provided(member));
+            if !present.is_empty() && !missing.is_empty() {
+                return Err(ParseError::PartialGroup { group: group.clone(), present, missing });
+            }
+        }
+
+        for arg in &parser_args {
+            if let ArgTypes::Param(false) = arg.arg_type && require_params && !forced {
+                return Err(ParseError::Missing);
+            }
+
+            if hashmap.contains_key(arg.key()) || defines.contains_key(arg.key()) {
+                continue;
+            }
+
+            let seed = self.seeds.borrow().get(arg.key()).cloned();
+            let default = || arg.default.as_ref().map(|default| (default.0)()).or_else(|| arg.default_value.clone());
+            if let Some(value) = seed.or_else(default) {
+                let value = arg.process_value(value)?;
+                hashmap.insert(String::from(arg.key()), Some(value));
+            }
+        }
+
+        let provided = |key: &str| hashmap.contains_key(key) || lists.contains_key(key) || defines.contains_key(key);
+        let missing: Vec<String> = parser_args.iter()
+            .filter(|arg| arg.required && !provided(arg.key()))
+            .filter(|arg| !arg.required_unless.iter().any(|other| provided(other)))
+            .map(|arg| arg.name.clone())
+            .collect();
+        if !missing.is_empty() && !forced {
+            return Err(ParseError::MissingRequired(missing));
+        }
+
+        let catch_all = self.catch_all.borrow().clone();
+        let filled = parser_args.iter()
+            .filter(|arg| matches!(arg.arg_type, ArgTypes::Param(true)))
+            .map(|arg| String::from(arg.key()))
+            .collect();
+        let trailing = self.trailing.borrow().clone();
+        let mode = !self.modes.borrow().is_empty();
+        Ok(ParseOutput { values: hashmap, lists, counts, defines, catch_all, warnings: vec![], bin_name: None, filled, args: parser_args, trailing, mode, stats: ParseStats::default(), forms: HashMap::new() })
+    }
+}
+
+/// How parsing takes a single token, decided by [`Reader::read()`].
+#[derive(Clone, Copy)]
+enum Reading<'a> {
+    /// The mode set with [`Parser::mode()`], which is always the first token.
+    Mode,
+    /// A flag enabled with `+` or disabled with `-` by [`Parser::plus_flags()`].
+    Toggle(&'a Arg),
+    /// A registered option, called by its long name, an alias or its short char.
+    Option(&'a Arg),
+    /// A cluster of shorts like `-xvf`, where only the last argument can take values.
+    Cluster,
+    /// An unknown long option collected by [`Parser::catch_all()`].
+    Caught,
+    /// The value taken by an unknown long option in [`CatchAllMode::NextValue`].
+    CaughtValue,
+    /// A value of the option before it, like that of an input or one of a group.
+    Value(&'a Arg),
+    /// The token ending the values of an [`Arg::terminator()`] input.
+    End,
+    /// A positional filling the param at this index.
+    Positional(usize),
+    /// A positional left over once every param is filled.
+    Extra,
+    /// The `--` options terminator.
+    Terminator,
+    /// A token after the terminator, collected by [`Parser::trailing_var_arg()`].
+    Trailing,
+    /// A token that doesn't match anything, like an unknown option.
+    Unknown,
+    /// The first positional or the `--` when reading stops there for [`Parser::parse_until_positional()`].
+    Rest,
+}
+
+/// Reads tokens one at a time the way parsing takes them.
+/// Parsing, [`Parser::explain()`] and the checks made before parsing all use this, so they can't disagree about a token.
+struct Reader<'a> {
+    parser: &'a Parser,
+    args: &'a [Arg],
+    mode: bool,
+    input: Option<&'a Arg>,
+    grouping: Option<(&'a Arg, usize)>,
+    collecting: Option<&'a Arg>,
+    catching: bool,
+    terminated: bool,
+    trailing: bool,
+    /// The indexes of the params filled so far.
+    filled: Vec<usize>,
+    /// The number of tokens read as positionals, including those left over once every param is filled.
+    positionals: usize,
+    dangling: Option<&'a Arg>,
+    /// Whether the first positional or `--` ends the tokens to parse, like the end of the options without interspersing.
+    until_positional: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(parser: &'a Parser, args: &'a [Arg]) -> Reader<'a> {
+        Reader {
+            parser,
+            args,
+            mode: !parser.modes.borrow().is_empty(),
+            input: None,
+            grouping: None,
+            collecting: None,
+            catching: false,
+            terminated: false,
+            trailing: false,
+            filled: vec![],
+            positionals: 0,
+            dangling: None,
+            until_positional: false,
+        }
+    }
+
+    /// Reads the next token, where `quoted` is set if it was quoted in the original command line.
+    fn read(&mut self, token: &str, quoted: bool) -> Reading<'a> {
+        let parser = self.parser;
+        // A quoted `--` is a plain value rather than the terminator or an option
+        let literal = quoted && token == "--";
+        let plain = literal || (token != "--" && !parser.is_option(self.args, token));
+
+        if std::mem::take(&mut self.mode) {
+            return Reading::Mode;
+        } else if self.trailing {
+            return Reading::Trailing;
+        }
+
+        if let Some(arg) = self.collecting {
+            if !quoted && arg.terminator.as_deref() == Some(token) {
+                self.collecting = None;
+                return Reading::End;
+            }
+            return Reading::Value(arg);
+        }
+
+        if let Some((arg, left)) = self.grouping.take() && plain {
+            self.grouping = Some((arg, left - 1)).filter(|(_, left)| *left > 0);
+            return Reading::Value(arg);
+        }
+
+        if let Some(arg) = self.input.take() {
+            if arg.raw_value || plain {
+                return Reading::Value(arg);
+            } else if token == "--" {
+                // An input can't take the terminator as its value
+                self.dangling.get_or_insert(arg);
+                return Reading::Unknown;
+            }
+        }
+
+        if std::mem::take(&mut self.catching) && !parser.is_option(self.args, token) {
+            return Reading::CaughtValue;
+        }
+
+        if self.terminated || plain {
+            return self.positional(token);
+        } else if token == "--" && self.until_positional {
+            return Reading::Rest;
+        } else if token == "--" {
+            // Stop reading options, and collect the rest of the tokens if a trailing arg was set
+            self.terminated = true;
+            self.trailing = parser.trailing.borrow().is_some();
+            return Reading::Terminator;
+        }
+
+        if let Some(arg) = parser.find_toggle(self.args, token).filter(|_| parser.plus_flags.get()) {
+            return Reading::Toggle(arg);
+        } else if token.starts_with("+") {
+            return Reading::Unknown;
+        } else if parser.catches(self.args, token) {
+            self.catching = parser.catch_all_mode.get() == CatchAllMode::NextValue && !token.contains('=');
+            return Reading::Caught;
+        }
+
+        let (reading, arg, attached) = match parser.find_option(self.args, token) {
+            Some(arg) => (Reading::Option(arg), arg, parser.attached(self.args, token).is_some()),
+            None => match split_cluster(self.args, token).and_then(|mut cluster| cluster.pop()) {
+                Some((arg, value)) => (Reading::Cluster, arg, value.is_some()),
+                None => return Reading::Unknown,
+            },
+        };
+        if arg.terminator.is_some() {
+            self.collecting = Some(arg);
+        } else if arg.max_positionals > 0 && !attached {
+            self.grouping = Some((arg, arg.max_positionals));
+        } else if arg.expecting && !attached {
+            self.input = Some(arg);
+        }
+        reading
+    }
+
+    /// Reads a positional, which fills the param it names or else the next one that's still empty.
+    fn positional(&mut self, token: &str) -> Reading<'a> {
+        if self.until_positional {
+            return Reading::Rest;
+        }
+        self.positionals += 1;
+        // Everything from the first positional on is positional if options can't be interspersed
+        self.terminated |= !self.parser.interspersed.get();
+
+        let index = match self.parser.named_param(self.args, token) {
+            Some((index, _)) => Some(index),
+            None => (0..self.args.len())
+                .find(|i| matches!(self.args[*i].arg_type, ArgTypes::Param(_)) && !self.filled.contains(i)),
+        };
+        match index {
+            Some(index) => {
+                self.filled.push(index);
+                Reading::Positional(index)
+            },
+            None => Reading::Extra,
+        }
+    }
+
+    /// Returns the first input left without its value, by an unquoted `--` or the end of the tokens read.
+    fn dangling(&self) -> Option<&'a Arg> {
+        self.dangling.or(self.input)
+    }
+
+    /// Describes how `token` was read, for [`Parser::explain()`] and [`Parser::on_token()`].
+    fn classify(&self, token: &str, reading: Reading) -> Classification {
+        let catch_all = || self.parser.catch_all.borrow().clone().unwrap_or_default();
+        match reading {
+            Reading::Mode => Classification::Mode,
+            Reading::Toggle(arg) | Reading::Option(arg) => match matched_form(arg, token) {
+                MatchedForm::Short(c) => Classification::ShortOption(c),
+                _ => Classification::LongOption(arg.name.clone()),
+            },
+            Reading::Cluster => Classification::ShortOption(token.chars().nth(1).unwrap_or_default()),
+            Reading::Caught => Classification::LongOption(catch_all()),
+            Reading::CaughtValue => Classification::Value(catch_all()),
+            Reading::Value(arg) => Classification::Value(arg.name.clone()),
+            Reading::End | Reading::Terminator => Classification::Terminator,
+            Reading::Positional(index) => Classification::Positional(self.args[index].name.clone()),
+            Reading::Trailing => Classification::Value(self.parser.trailing.borrow().clone().unwrap_or_default()),
+            Reading::Extra | Reading::Unknown | Reading::Rest => Classification::Unknown,
+        }
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser::new()
+    }
+}
+
+impl IntoIterator for Parser {
+    type Item = Arg;
+    type IntoIter = std::vec::IntoIter<Arg>;
+
+    /// Consumes the parser, iterating over its arguments in the order they were added.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().param("file"), Arg::new().flag("verbose")]);
+    /// 
+    /// for arg in parser {
+    ///     println!("{}", arg.get_name());
+    /// }
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        self.args.into_inner().into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Parser {
+    type Item = Ref<'a, Arg>;
+    type IntoIter = ParserArgs<'a>;
+
+    /// Iterates over the parser's arguments in the order they were added, borrowing each one without cloning it.
+    /// 
+    /// # Example
+    /// ```
+    /// # use simple_cli_parser::{Arg, Parser};
+    /// let parser = Parser::new();
+    /// parser.add_args(vec![Arg::new().param("file"), Arg::new().flag("verbose")]);
+    /// 
+    /// for arg in &parser {
+    ///     println!("{}", arg.get_name());
+    /// }
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        ParserArgs { args: self.args.borrow(), index: 0 }
+    }
+}
+
+/// An iterator over the arguments of a borrowed [`Parser`].
+/// 
+/// Since the arguments live behind a `RefCell`, each one is a [`Ref`] into the parser rather than a plain reference.
+/// The arguments stay borrowed until the iterator and every `Ref` it gave are dropped,
+/// so adding arguments to the parser in the meantime panics.
+pub struct ParserArgs<'a> {
+    args: Ref<'a, Vec<Arg>>,
+    index: usize,
+}
+
+impl<'a> Iterator for ParserArgs<'a> {
+    type Item = Ref<'a, Arg>;
+
+    fn next(&mut self) -> Option<Ref<'a, Arg>> {
+        let arg = Ref::filter_map(Ref::clone(&self.args), |args| args.get(self.index)).ok()?;
+        self.index += 1;
+        Some(arg)
+    }
+}
+
+
+// Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_arg() {
+        let default = Arg::new().input("default");
+        assert_eq!(default.name, "default");
+        assert!(default.expecting);
+        
+        let short = Arg::new().input("short").short('s');
+        assert_eq!(short.name, "short");
+        assert_eq!(short.short, Some('s'));
+        assert!(short.expecting);
+
+        let flag = Arg::new().flag("flag").short( 'f');
+        assert!(!flag.expecting);
+        assert_eq!(flag.short, Some('f'));
+        assert_eq!(flag.name, "flag");
+
+        let mut param = Arg::new().param("param");
+        assert!(!param.expecting);
+        if let ArgTypes::Param(used) = param.arg_type {
+            assert!(!used);
+        } else {
+            panic!();
+        }
+        assert_eq!(param.name, "param");
+
+        param.set_used(true);
+        if let ArgTypes::Param(used) = param.arg_type {
+            assert!(used);
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn append_args() {
+        let default = Arg::new().input("default");
+        let short = Arg::new().input("short").short('s');
+        let flag = Arg::new().flag("flag").short( 'f');
+
+        let parser = Parser::new();
+        parser.add_arg(default);
+        assert_eq!(parser.len(), 1);
+        parser.add_args(vec![short, flag]);
+        assert_eq!(parser.len(), 3);
+        assert_eq!(parser.args()[2].name, "flag");
+    }
+
+    #[test]
+    fn test_parse_err() {
+        let default = Arg::new().input("default");
+        let short = Arg::new().input("short").short('s');
+        let flag = Arg::new().flag("flag").short( 'f');
+        let param1 = Arg::new().param("p1");
+        let param2 = Arg::new().param("p2");
+
+        let parser = Parser::new();
+        parser.add_args(vec![default, short, flag, param1, param2]);
+
+        // Tests err on duplicate value
+        let mut cmd = "--short short_inp -s s_inp p1 p2"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+
+        let res = parser.parse(&mut cmd);
+        assert!(res.is_err());
+        
+        // Tests err on invalid command order
+        let mut cmd = "--default -f p1 p2"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+
+        let res = parser.parse(&mut cmd);
+        assert!(res.is_err());
+
+        // Tests err on unexpected token
+        let mut cmd = "p1 p2 -f dsjfhkjuh"
+        .split_whitespace()
+        .map(|s| { String::from(s) });
+
+        let res = parser.parse(&mut cmd);
+        assert!(res.is_err());
+
+        // Tests err on missing token
+        let mut cmd = "p1 p2 --default"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+
+        let res = parser.parse(&mut cmd);
+        assert!(res.is_err());
+
+        // Tests err on missing param
+        let mut cmd = "p1 -f"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+
+        let res = parser.parse(&mut cmd);
+        assert!(res.is_err());
+
+        // Tests err on unrecognized arg
+        let mut cmd = "p1 p2 --doesntexist"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+
+        let res = parser.parse(&mut cmd);
+        assert!(res.is_err());
+
+    }
+
+    #[test]
+    pub fn test_parse_success() {
+        let default = Arg::new().input("default");
+        let short = Arg::new().input("short").short('s');
+        let flag = Arg::new().flag("flag").short( 'f');
+        let param1 = Arg::new().param("file");
+        let param2 = Arg::new().param("path");
+
+        let parser = Parser::new();
+        parser.add_args(vec![default, short, flag, param1, param2]);
+
+        let mut cmd = "--default def_arg filename -s s_arg -f pathname"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+
+        let res = parser.parse(&mut cmd);
+        assert!(res.is_ok());
+
+        let res = res.unwrap();
+        assert_eq!(res.len(), 5);
+        assert!(res.contains_key("default"));
+        assert_eq!(res.get("default").unwrap(), &Some(String::from("def_arg")));
+        assert!(res.contains_key("short"));
+        assert_eq!(res.get("short").unwrap(), &Some(String::from("s_arg")));
+        assert!(res.contains_key("flag"));
+        assert_eq!(res.get("flag").unwrap(), &None);
+        assert!(res.contains_key("file"));
+        assert_eq!(res.get("file").unwrap(), &Some(String::from("filename")));
+        assert!(res.contains_key("path"));
+        assert_eq!(res.get("path").unwrap(), &Some(String::from("pathname")));
+    }
+
+    #[test]
+    fn test_parse_short() {
+        let help = Arg::new().flag("help").short('h');
+
+        let parser = Parser::new();
+        parser.add_arg(help);
+
+        let mut cmd = "-h"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+
+        let res = parser.parse(&mut cmd).unwrap();
+        assert!(res.contains_key("help"));
+
+        // Only the char directly after the dash may match
+        let mut cmd = "-xh"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+
+        let res = parser.parse(&mut cmd);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_strict_ordering() {
+        let flag = Arg::new().flag("flag").short('f');
+        let param = Arg::new().param("p1");
+
+        let parser = Parser::new();
+        parser.add_args(vec![flag, param]);
+
+        // Options after params are allowed by default
+        let mut cmd = "p1 -f"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        assert!(parser.parse(&mut cmd).is_ok());
+
+        let mut cmd = "-f p1"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        assert!(parser.parse(&mut cmd).is_ok());
+
+        parser.strict_ordering(true);
+
+        let mut cmd = "-f p1"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        assert!(parser.parse(&mut cmd).is_ok());
+
+        let mut cmd = "p1 -f"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        let err = parser.parse(&mut cmd).unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(err, &ParseError::OptionAfterPositional(String::from("-f")));
+    }
+
+    #[test]
+    fn test_missing_required() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().param("p1"),
+            Arg::new().flag("flag"),
+            Arg::new().input("inp"),
+            Arg::new().param("p2"),
+        ]);
+
+        assert_eq!(parser.missing_required(&[]), vec!["p1", "p2"]);
+        assert_eq!(parser.missing_required(&["p2", "flag"]), vec!["p1"]);
+        assert!(parser.missing_required(&["p1", "p2"]).is_empty());
+    }
+
+    #[test]
+    fn test_merge() {
+        let parser = Parser::new();
+        parser.add_arg(Arg::new().flag("verbose").short('v'));
+
+        let network = Parser::new();
+        network.add_args(vec![Arg::new().input("port"), Arg::new().input("host")]);
+        network.strict_ordering(true);
+
+        assert!(parser.merge(&network).is_ok());
+        assert_eq!(parser.len(), 3);
+        assert_eq!(parser.args()[1].name, "port");
+        assert!(parser.strict_ordering.get());
+
+        // Duplicate names are rejected and leave the parser untouched
+        let dup = Parser::new();
+        dup.add_args(vec![Arg::new().flag("quiet"), Arg::new().input("port")]);
+
+        assert!(parser.merge(&dup).is_err());
+        assert_eq!(parser.len(), 3);
+
+        // So are shorts, which would make every later parse ambiguous
+        let short = Parser::new();
+        short.add_arg(Arg::new().flag("version").short('v'));
+
+        assert_eq!(parser.merge(&short), Err(ConfigError::DuplicateShort('v')));
+        assert_eq!(parser.len(), 3);
+        assert!(parser.try_get_matches_from(["-v"]).is_ok());
+    }
+
+    #[test]
+    fn test_arg_long() {
+        let output = Arg::new().input_kind().long("output").short('o');
+        assert_eq!(output.name, "output");
+        assert_eq!(output.short, Some('o'));
+        assert!(output.expecting);
+
+        // Builder order does not matter
+        let reordered = Arg::new().short('o').long("output").input_kind();
+        assert_eq!(reordered.name, "output");
+        assert_eq!(reordered.short, Some('o'));
+        assert!(reordered.expecting);
+
+        // A short param is still a param
+        let param = Arg::new().param("file").short('f');
+        assert!(matches!(param.arg_type, ArgTypes::Param(false)));
+
+        let parser = Parser::new();
+        parser.add_args(vec![output, param]);
+
+        let mut cmd = "-o out.txt in.txt"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+
+        let res = parser.parse(&mut cmd).unwrap();
+        assert_eq!(res.get("output").unwrap(), &Some(String::from("out.txt")));
+        assert_eq!(res.get("file").unwrap(), &Some(String::from("in.txt")));
+    }
+
+    #[test]
+    fn test_plus_flags() {
+        let trace = Arg::new().flag("trace").short('x');
+        let verbose = Arg::new().flag("verbose").short('v');
+        let out = Arg::new().input("out").short('o');
+        let param = Arg::new().param("p1");
+
+        let parser = Parser::new();
+        parser.add_args(vec![trace, verbose, out, param]);
+
+        // '+' tokens are positionals by default
+        let mut cmd = "+x"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        let res = parser.parse(&mut cmd).unwrap();
+        assert_eq!(res.get("p1").unwrap(), &Some(String::from("+x")));
+
+        parser.plus_flags(true);
+
+        let mut cmd = "+x -verbose -o file p1"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        let res = parser.parse(&mut cmd).unwrap();
+        assert_eq!(res.get("trace").unwrap(), &Some(String::from("true")));
+        assert_eq!(res.get("verbose").unwrap(), &Some(String::from("false")));
+        assert_eq!(res.get("out").unwrap(), &Some(String::from("file")));
+
+        let mut cmd = "+trace -v --out file p1"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        let res = parser.parse(&mut cmd).unwrap();
+        assert_eq!(res.get("trace").unwrap(), &Some(String::from("true")));
+        assert_eq!(res.get("verbose").unwrap(), &Some(String::from("false")));
+
+        // Long form still behaves like a regular flag
+        let mut cmd = "--trace p1"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        let res = parser.parse(&mut cmd).unwrap();
+        assert_eq!(res.get("trace").unwrap(), &None);
+
+        // Inputs can't be toggled
+        let mut cmd = "+o p1"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        assert!(parser.parse(&mut cmd).is_err());
+
+        let mut cmd = "+x -x p1"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        assert!(parser.parse(&mut cmd).is_err());
+    }
+
+    #[test]
+    fn test_positional_count() {
+        let out = Arg::new().input("out").short('o');
+        let flag = Arg::new().flag("flag").short('f');
+
+        let parser = Parser::new();
+        parser.add_args(vec![out, flag, Arg::new().param("p1"), Arg::new().param("p2"), Arg::new().param("p3")]);
+
+        let mut cmd = "a -o out b c d e"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        let err = parser.parse(&mut cmd).unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(err, &ParseError::TooManyPositionals { expected: 3, got: 5 });
+        assert_eq!(err.to_string(), "Invalid command, too many positional arguments: expected 3, got 5");
+
+        let mut cmd = "-f a --out out"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        let err = parser.parse(&mut cmd).unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(err, &ParseError::TooFewPositionals { expected: 3, got: 1 });
+        assert_eq!(err.to_string(), "Invalid command, missing positional arguments: expected 3, got 1");
+
+        // Values of inputs aren't counted as positionals
+        let mut cmd = "a -o out b -f c"
+            .split_whitespace()
+            .map(|s| { String::from(s) });
+        assert!(parser.parse(&mut cmd).is_ok());
+    }
+
+    #[test]
+    fn test_get_matches_from() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("out").short('o'),
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().param("file"),
+        ]);
+
+        let res = parser.try_get_matches_from(["-v", "-o", "out.txt", "in.txt"]).unwrap();
+        assert_eq!(res.len(), 3);
+        assert!(res.is_present("verbose"));
+        assert_eq!(res.value_of("verbose"), None);
+        assert_eq!(res.value_of("out"), Some("out.txt"));
+        assert_eq!(res.value_of("file"), Some("in.txt"));
+
+        let args: Vec<String> = vec![String::from("in.txt")];
+        let res = parser.try_get_matches_from(args.clone()).unwrap();
+        assert_eq!(res.value_of("file"), Some("in.txt"));
+        assert!(!res.is_present("out"));
+        assert_eq!(parser.get_matches_from(args), res);
+
+        // The first item is not skipped
+        let res = parser.try_get_matches_from(["prog", "in.txt"]);
+        assert_eq!(res, Err(ParseError::TooManyPositionals { expected: 1, got: 2 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected token '--nope'")]
+    fn test_get_matches_from_panics() {
+        let parser = Parser::new();
+        let _ = parser.get_matches_from(["--nope"]);
+    }
+
+    #[test]
+    fn test_take_next_raw() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("exclude").short('e').take_next_raw(),
+            Arg::new().input("out"),
+            Arg::new().flag("verbose").short('v'),
+        ]);
+
+        let res = parser.try_get_matches_from(["--exclude", "--verbose", "-v"]).unwrap();
+        assert_eq!(res.value_of("exclude"), Some("--verbose"));
+        assert!(res.is_present("verbose"));
+
+        let res = parser.try_get_matches_from(["-e", "-x"]).unwrap();
+        assert_eq!(res.value_of("exclude"), Some("-x"));
+
+        // Regular inputs still reject option-like values
+        let res = parser.try_get_matches_from(["--out", "--verbose"]);
+        assert_eq!(res, Err(ParseError::Unexpected(String::from("--verbose"))));
+
+        let res = parser.try_get_matches_from(["-v", "--exclude"]);
+        assert_eq!(res, Err(ParseError::MissingValue(String::from("exclude"))));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_to_json() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().input("name"),
+            Arg::new().param("file"),
+        ]);
+
+        let res = parser.try_get_matches_from(["-v", "--name", "say \"hi\"\n", "C:\\in.txt"]).unwrap();
+        assert_eq!(res.to_json(), r#"{"file":"C:\\in.txt","name":"say \"hi\"\n","verbose":true}"#);
+
+        let res = parser.try_get_matches_from(["in.txt"]).unwrap();
+        assert_eq!(res.to_json(), r#"{"file":"in.txt"}"#);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().param("file"),
+            Arg::new().flag("verbose"),
+            Arg::new().input("out"),
+        ]);
+
+        let names: Vec<String> = (&parser).into_iter().map(|arg| arg.name.clone()).collect();
+        assert_eq!(names, vec!["file", "verbose", "out"]);
+
+        // Borrowing iteration leaves the parser usable once it's done
+        let mut args = (&parser).into_iter();
+        assert_eq!(args.next().map(|arg| arg.name.clone()).as_deref(), Some("file"));
+        assert_eq!(parser.len(), 3);
+        drop(args);
+        parser.add_arg(Arg::new().flag("quiet"));
+        assert_eq!((&parser).into_iter().count(), 4);
+
+        let names: Vec<String> = parser.into_iter().map(|arg| arg.name).collect();
+        assert_eq!(names, vec!["file", "verbose", "out", "quiet"]);
+    }
+
+    #[test]
+    fn test_terminator() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().param("cmd"),
+            Arg::new().param("file"),
+            Arg::new().flag("verbose").short('v'),
+        ]);
+
+        // Without a trailing arg, tokens after `--` are positionals
+        let res = parser.try_get_matches_from(["-v", "--", "-x", "--inner"]).unwrap();
+        assert!(res.is_present("verbose"));
+        assert_eq!(res.value_of("cmd"), Some("-x"));
+        assert_eq!(res.value_of("file"), Some("--inner"));
+
+        let res = parser.try_get_matches_from(["run", "--", "-v", "--inner", "-x"]);
+        assert_eq!(res, Err(ParseError::TooManyPositionals { expected: 2, got: 4 }));
+    }
+
+    #[test]
+    fn test_trailing_var_arg() {
+        let parser = Parser::new();
+        parser.add_args(vec![Arg::new().param("cmd"), Arg::new().flag("verbose").short('v')]);
+        parser.trailing_var_arg("args");
+
+        let res = parser.try_get_matches_from(["run", "--", "--inner", "-x"]).unwrap();
+        assert_eq!(res.value_of("cmd"), Some("run"));
+        assert_eq!(res.values_of("args").unwrap(), ["--inner", "-x"]);
+        assert!(!res.is_present("verbose"));
+        #[cfg(feature = "serde")]
+        assert_eq!(res.to_json(), r#"{"args":["--inner","-x"],"cmd":"run"}"#);
+
+        let res = parser.try_get_matches_from(["run", "-v", "--"]).unwrap();
+        assert!(res.values_of("args").unwrap().is_empty());
+
+        let res = parser.try_get_matches_from(["run"]).unwrap();
+        assert_eq!(res.values_of("args"), None);
+
+        // Params must still be filled before the terminator
+        let res = parser.try_get_matches_from(["--", "run"]);
+        assert_eq!(res, Err(ParseError::TooFewPositionals { expected: 1, got: 0 }));
+    }
+
+    #[test]
+    fn test_canonical_name() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("color").short('c').alias("colour"),
+            Arg::new().flag("help").short('h').alias("usage"),
+        ]);
+
+        for cmd in [["--color", "red", "--help"], ["-c", "red", "-h"], ["--colour", "red", "--usage"]] {
+            let res = parser.try_get_matches_from(cmd).unwrap();
+            let mut keys: Vec<String> = res.into_map().into_keys().collect();
+            keys.sort();
+            assert_eq!(keys, vec!["color", "help"]);
+        }
+
+        // Calling the same arg two different ways is still a duplicate
+        let res = parser.try_get_matches_from(["--help", "--usage"]);
+        assert_eq!(res, Err(ParseError::Duplicate(String::from("--usage"))));
+    }
+
+    #[test]
+    fn test_value_parsers() {
+        assert_eq!(parse_byte_size("512"), Some(512));
+        assert_eq!(parse_byte_size("10MB"), Some(10_000_000));
+        assert_eq!(parse_byte_size("4KiB"), Some(4096));
+        assert_eq!(parse_byte_size("2gib"), Some(2 << 30));
+        assert_eq!(parse_byte_size("MB"), None);
+        assert_eq!(parse_byte_size("10XB"), None);
+        assert_eq!(parse_byte_size("99999999999TB"), None);
+
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("30"), None);
+        assert_eq!(parse_duration("1.5s"), None);
+
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("max-size").byte_size(),
+            Arg::new().input("timeout").duration(),
+            Arg::new().param("wait").duration(),
+        ]);
+
+        let res = parser.try_get_matches_from(["--max-size", "1KB", "--timeout", "2m", "10ms"]).unwrap();
+        assert_eq!(res.value_of("max-size"), Some("1000"));
+        assert_eq!(res.byte_size_of("max-size"), Some(1000));
+        assert_eq!(res.duration_of("timeout"), Some(Duration::from_secs(120)));
+        assert_eq!(res.duration_of("wait"), Some(Duration::from_millis(10)));
+
+        let res = parser.try_get_matches_from(["--timeout", "soon", "10ms"]);
+        let err = ParseError::Value {
+            name: String::from("timeout"),
+            value: String::from("soon"),
+            kind: ValueErrorKind::Type("a duration"),
+        };
+        assert_eq!(res, Err(err.clone()));
+        assert_eq!(err.to_string(), "Invalid command, invalid value 'soon' for 'timeout': expected a duration");
+    }
+
+    #[test]
+    fn test_parse_with_program_name() {
+        let parser = Parser::new();
+        parser.add_arg(Arg::new().flag("flag"));
+
+        let mut cmd = ["prog", "--flag"].into_iter().map(String::from);
+        let res = parser.parse_with_program_name(&mut cmd).unwrap();
+        assert_eq!(res.bin_name(), Some("prog"));
+        assert!(res.is_present("flag"));
+        assert_eq!(res.len(), 1);
+
+        let res = parser.parse_with_program_name(&mut std::iter::empty()).unwrap();
+        assert_eq!(res.bin_name(), None);
+        assert!(res.is_empty());
+    }
+
+    #[test]
+    fn test_explain() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("out").short('o'),
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().param("file"),
+        ]);
+
+        let explained = parser.explain(["--verbose", "-o", "a.txt", "b.txt", "-x", "c.txt", "--", "-v"]);
+        let classifications: Vec<Classification> = explained.iter().map(|(_, c)| c.clone()).collect();
+        assert_eq!(explained[0].0, "--verbose");
+        assert_eq!(classifications, vec![
+            Classification::LongOption(String::from("verbose")),
+            Classification::ShortOption('o'),
+            Classification::Value(String::from("out")),
+            Classification::Positional(String::from("file")),
+            Classification::Unknown,
+            Classification::Unknown,
+            Classification::Terminator,
+            Classification::Unknown,
+        ]);
+
+        // A missing value doesn't stop the rest from being classified
+        parser.trailing_var_arg("rest");
+        let explained = parser.explain(["-o", "-v", "a.txt", "--", "-v"]);
+        let classifications: Vec<Classification> = explained.into_iter().map(|(_, c)| c).collect();
+        assert_eq!(classifications, vec![
+            Classification::ShortOption('o'),
+            Classification::ShortOption('v'),
+            Classification::Positional(String::from("file")),
+            Classification::Terminator,
+            Classification::Value(String::from("rest")),
+        ]);
+
+        // Tokens are read the same way parsing takes them, even for a cluster ending in a terminated input
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().input("files").short('f').values_until_terminator(),
+            Arg::new().param("cmd"),
+        ]);
+        parser.catch_all("extra");
+
+        let tokens = ["-vf", "a", "--", "--color", "red", "run"];
+        let classifications: Vec<Classification> = parser.explain(tokens).into_iter().map(|(_, c)| c).collect();
+        assert_eq!(classifications, vec![
+            Classification::ShortOption('v'),
+            Classification::Value(String::from("files")),
+            Classification::Terminator,
+            Classification::LongOption(String::from("extra")),
+            Classification::Value(String::from("extra")),
+            Classification::Positional(String::from("cmd")),
+        ]);
+        let output = parser.try_get_matches_from(tokens).unwrap();
+        assert_eq!(output.extras()["color"], "red");
+        assert_eq!(output.value_of("cmd"), Some("run"));
+    }
+
+    #[test]
+    fn test_ambiguous_short() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("extract").short('x'),
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().input("exclude").short('x'),
+        ]);
+
+        // The configuration is rejected even when the short isn't used
+        assert_eq!(parser.try_get_matches_from(["-x"]), Err(ParseError::AmbiguousShort('x')));
+        assert_eq!(parser.try_get_matches_from(["-v"]), Err(ParseError::AmbiguousShort('x')));
+    }
+
+    #[test]
+    fn test_map_value() {
+        let home = String::from("/home/user");
+        let expand = move |v: String| match v.strip_prefix('~') {
+            Some(rest) => format!("{}{}", home, rest),
+            None => v,
+        };
+
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("config").map_value(expand),
+            Arg::new().param("size").map_value(|v| v.trim().to_uppercase()).byte_size(),
+        ]);
+
+        let res = parser.try_get_matches_from(["--config", "~/app.toml", " 2kib "]).unwrap();
+        assert_eq!(res.value_of("config"), Some("/home/user/app.toml"));
+        assert_eq!(res.byte_size_of("size"), Some(2048));
+
+        let res = parser.try_get_matches_from(["--config", "/etc/app.toml", "2xb"]);
+        assert_eq!(res, Err(ParseError::Value {
+            name: String::from("size"),
+            value: String::from("2XB"),
+            kind: ValueErrorKind::Type("a byte size"),
+        }));
+
+        // Cloned args share the transformation
+        let arg = parser.args()[0].clone();
+        assert_eq!(arg.process_value(String::from("~")), Ok(String::from("/home/user")));
+    }
+
+    #[test]
+    fn test_default_with() {
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("jobs").default_with(move || {
+                counter.set(counter.get() + 1);
+                String::from("8")
+            }),
+            Arg::new().input("limit").byte_size().default_with(|| String::from("1KiB")),
+        ]);
+
+        let res = parser.try_get_matches_from(["--jobs", "2"]).unwrap();
+        assert_eq!(res.value_of("jobs"), Some("2"));
+        assert_eq!(res.byte_size_of("limit"), Some(1024));
+        assert_eq!(calls.get(), 0);
+
+        let res = parser.try_get_matches_from(Vec::<String>::new()).unwrap();
+        assert_eq!(res.value_of("jobs"), Some("8"));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_implies() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("all").short('a').implies(&["verbose", "recursive"]),
+            Arg::new().flag("verbose").short('v').implies(&["log"]),
+            Arg::new().flag("recursive"),
+            Arg::new().flag("log").implies(&["verbose"]),
+            Arg::new().flag("quiet"),
+        ]);
+
+        // Chained implications
+        let res = parser.try_get_matches_from(["-a"]).unwrap();
+        assert_eq!(res.len(), 4);
+        assert!(res.is_present("verbose"));
+        assert!(res.is_present("recursive"));
+        assert!(res.is_present("log"));
+        assert!(!res.is_present("quiet"));
+
+        // Explicitly passing an implied flag isn't a duplicate
+        let res = parser.try_get_matches_from(["-v", "--all"]).unwrap();
+        assert_eq!(res.len(), 4);
+
+        // verbose -> log -> verbose is a cycle
+        let res = parser.try_get_matches_from(["--log"]).unwrap();
+        assert_eq!(res.len(), 2);
+        assert!(res.is_present("verbose"));
+
+        // Toggled off flags imply nothing
+        parser.plus_flags(true);
+        let res = parser.try_get_matches_from(["-all"]).unwrap();
+        assert_eq!(res.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_into_struct() {
+        #[derive(Debug, PartialEq)]
+        struct Config {
+            jobs: u32,
+            name: String,
+            verbose: bool,
+        }
+
+        impl FromMatches for Config {
+            fn from_matches(m: &ParseOutput) -> Result<Self, ParseError> {
+                let jobs = m.value_of("jobs").unwrap_or("1");
+                Ok(Config {
+                    jobs: jobs.parse().map_err(|_| ParseError::Value {
+                        name: String::from("jobs"),
+                        value: String::from(jobs),
+                        kind: ValueErrorKind::Type("an integer"),
+                    })?,
+                    name: m.value_of("name").ok_or(ParseError::Missing)?.to_string(),
+                    verbose: m.is_present("verbose"),
+                })
+            }
+        }
+
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("jobs").short('j'),
+            Arg::new().param("name"),
+            Arg::new().flag("verbose").short('v'),
+        ]);
+
+        let mut cmd = ["-j", "4", "build", "-v"].into_iter().map(String::from);
+        let config: Config = parser.parse_into_struct(&mut cmd).unwrap();
+        assert_eq!(config, Config { jobs: 4, name: String::from("build"), verbose: true });
+
+        let mut cmd = ["build"].into_iter().map(String::from);
+        let config: Config = parser.parse_into_struct(&mut cmd).unwrap();
+        assert_eq!(config, Config { jobs: 1, name: String::from("build"), verbose: false });
+
+        let mut cmd = ["-j", "four", "build"].into_iter().map(String::from);
+        let res: Result<Config, ParseError> = parser.parse_into_struct(&mut cmd);
+        assert!(matches!(res, Err(ParseError::Value { .. })));
+
+        // Parse errors are returned before the struct is built
+        let mut cmd = ["-x"].into_iter().map(String::from);
+        let res: Result<Config, ParseError> = parser.parse_into_struct(&mut cmd);
+        assert!(matches!(res, Err(ParseError::TooFewPositionals { .. })));
+    }
+
+    #[test]
+    fn test_on_duplicate() {
+        let parser = Parser::new();
+        parser.add_args(vec![Arg::new().input("level").short('l'), Arg::new().flag("verbose")]);
+
+        let cmd = ["--level", "info", "-l", "debug", "--verbose", "--verbose"];
+
+        let res = parser.try_get_matches_from(cmd);
+        assert_eq!(res, Err(ParseError::Duplicate(String::from("debug"))));
+
+        parser.on_duplicate(DuplicatePolicy::LastWins);
+        let res = parser.try_get_matches_from(cmd).unwrap();
+        assert_eq!(res.value_of("level"), Some("debug"));
+        assert!(res.is_present("verbose"));
+
+        parser.on_duplicate(DuplicatePolicy::FirstWins);
+        let res = parser.try_get_matches_from(cmd).unwrap();
+        assert_eq!(res.value_of("level"), Some("info"));
+        assert!(res.is_present("verbose"));
+    }
+
+    #[test]
+    fn test_negative_numbers() {
+        assert!(is_negative_number("-5"));
+        assert!(is_negative_number("-3.14"));
+        assert!(!is_negative_number("-5x"));
+        assert!(!is_negative_number("-.5"));
+        assert!(!is_negative_number("-5."));
+        assert!(!is_negative_number("-"));
+        assert!(!is_negative_number("5"));
+
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("offset").short('o'),
+            Arg::new().flag("one").short('1'),
+            Arg::new().param("num"),
+        ]);
+
+        let res = parser.try_get_matches_from(["-5"]).unwrap();
+        assert_eq!(res.value_of("num"), Some("-5"));
+
+        let res = parser.try_get_matches_from(["-o", "-3.14", "-1", "-2"]).unwrap();
+        assert_eq!(res.value_of("offset"), Some("-3.14"));
+        assert!(res.is_present("one"));
+        assert_eq!(res.value_of("num"), Some("-2"));
+
+        let res = parser.try_get_matches_from(["-5x", "7"]);
+        assert_eq!(res, Err(ParseError::Unexpected(String::from("-5x"))));
+
+        parser.allow_negative_numbers(false);
+        let res = parser.try_get_matches_from(["-5"]);
+        assert_eq!(res, Err(ParseError::TooFewPositionals { expected: 1, got: 0 }));
+    }
+
+    #[test]
+    fn test_args_sorted() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("verbose"),
+            Arg::new().input("config"),
+            Arg::new().param("file"),
+            Arg::new().flag("all"),
+        ]);
+
+        let names: Vec<String> = parser.args().into_iter().map(|arg| arg.name).collect();
+        assert_eq!(names, vec!["verbose", "config", "file", "all"]);
+
+        let names: Vec<String> = parser.args_sorted().into_iter().map(|arg| arg.name).collect();
+        assert_eq!(names, vec!["all", "config", "file", "verbose"]);
+    }
+
+    #[test]
+    fn test_count() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("verbose").short('v').count(),
+            Arg::new().flag("quiet").short('q'),
+        ]);
+
+        let res = parser.try_get_matches_from(["-v", "--verbose", "-v"]).unwrap();
+        assert_eq!(res.count("verbose"), 3);
+        assert!(res.is_present("verbose"));
+        assert_eq!(res.count("quiet"), 0);
+
+        let res = parser.try_get_matches_from(["-q"]).unwrap();
+        assert_eq!(res.count("verbose"), 0);
+
+        // Non-countable flags still follow the duplicate policy
+        let res = parser.try_get_matches_from(["-v", "-q", "-v", "-q"]);
+        assert_eq!(res, Err(ParseError::Duplicate(String::from("-q"))));
+    }
+
+    #[test]
+    fn test_short_equals() {
+        assert_eq!(split_short("-o"), Some(('o', None)));
+        assert_eq!(split_short("-o=foo"), Some(('o', Some("foo"))));
+        assert_eq!(split_short("-o=a=b"), Some(('o', Some("a=b"))));
+        assert_eq!(split_short("-o="), Some(('o', Some(""))));
+        assert_eq!(split_short("-of"), None);
+        assert_eq!(split_short("--o"), None);
+
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("out").short('o'),
+            Arg::new().flag("extract").short('x'),
+            Arg::new().param("file"),
+        ]);
+
+        let res = parser.try_get_matches_from(["-o=foo", "in.txt"]).unwrap();
+        assert_eq!(res.value_of("out"), Some("foo"));
+        assert_eq!(res.value_of("file"), Some("in.txt"));
+
+        let res = parser.try_get_matches_from(["in.txt", "-o="]).unwrap();
+        assert_eq!(res.value_of("out"), Some(""));
+
+        let res = parser.try_get_matches_from(["-x=foo", "in.txt"]);
+        assert_eq!(res, Err(ParseError::Unexpected(String::from("-x=foo"))));
+
+        let explained = parser.explain(["-o=foo", "in.txt"]);
+        assert_eq!(explained[0].1, Classification::ShortOption('o'));
+        assert_eq!(explained[1].1, Classification::Positional(String::from("file")));
+    }
+
+    #[test]
+    fn test_capacity() {
+        let parser = Parser::with_capacity(32);
+        assert!(parser.args.borrow().capacity() >= 32);
+        assert!(parser.is_empty());
+
+        parser.add_arg(Arg::new().flag("flag"));
+        parser.reserve(100);
+        assert!(parser.args.borrow().capacity() >= 101);
+        assert_eq!(parser.len(), 1);
+    }
+
+    #[test]
+    fn test_validate() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("all").short('a').implies(&["verbose"]),
+            Arg::new().flag("verbose").short('v').alias("loud"),
+            Arg::new().param("file"),
+        ]);
+        assert_eq!(parser.validate(), Ok(()));
+
+        let copy = || {
+            let copy = Parser::new();
+            copy.add_args(parser.args());
+            copy
+        };
+
+        let dup_name = copy();
+        dup_name.add_arg(Arg::new().input("file"));
+        assert_eq!(dup_name.validate(), Err(ConfigError::DuplicateName(String::from("file"))));
+
+        let dup_alias = copy();
+        dup_alias.add_arg(Arg::new().flag("loud"));
+        assert_eq!(dup_alias.validate(), Err(ConfigError::DuplicateName(String::from("loud"))));
+
+        let dup_short = copy();
+        dup_short.add_arg(Arg::new().flag("version").short('v'));
+        assert_eq!(dup_short.validate(), Err(ConfigError::DuplicateShort('v')));
+
+        // A flag and a param would share a key in the output
+        let dup_kind = copy();
+        dup_kind.add_arg(Arg::new().flag("x"));
+        dup_kind.add_arg(Arg::new().param("x"));
+        assert_eq!(dup_kind.validate(), Err(ConfigError::DuplicateName(String::from("x"))));
+
+        let dup_key = copy();
+        dup_key.add_arg(Arg::new().param("target").id("verbose"));
+        assert_eq!(dup_key.validate(), Err(ConfigError::DuplicateKey(String::from("verbose"))));
+
+        let unknown = copy();
+        unknown.add_arg(Arg::new().flag("debug").implies(&["trace"]));
+        assert_eq!(unknown.validate(), Err(ConfigError::UnknownReference {
+            arg: String::from("debug"),
+            reference: String::from("trace"),
+        }));
+    }
+
+    #[test]
+    fn test_with_defaults_from() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("host"),
+            Arg::new().input("port").default_with(|| String::from("80")),
+            Arg::new().input("timeout").duration(),
+            Arg::new().input("user"),
+        ]);
+        parser.with_defaults_from(HashMap::from([
+            (String::from("host"), String::from("localhost")),
+            (String::from("port"), String::from("8080")),
+            (String::from("timeout"), String::from("5s")),
+            (String::from("unknown"), String::from("ignored")),
+        ]));
+
+        let res = parser.try_get_matches_from(["--host", "example.com"]).unwrap();
+        assert_eq!(res.value_of("host"), Some("example.com"));
+        assert_eq!(res.value_of("port"), Some("8080"));
+        assert_eq!(res.duration_of("timeout"), Some(Duration::from_secs(5)));
+        assert!(!res.is_present("user"));
+        assert!(!res.is_present("unknown"));
+        assert_eq!(res.len(), 3);
+    }
+
+    #[test]
+    fn test_defines() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("define").short('D').defines(),
+            Arg::new().flag("verbose"),
+        ]);
+
+        let res = parser.try_get_matches_from(["-D", "a=1", "-D", "b=2", "-D=c=x=y", "--define", "flag", "-D", "a=3"]).unwrap();
+        let defines = res.defines_of("define").unwrap();
+        assert_eq!(defines.len(), 4);
+        assert_eq!(defines["a"], "3");
+        assert_eq!(defines["b"], "2");
+        assert_eq!(defines["c"], "x=y");
+        assert_eq!(defines["flag"], "true");
+        assert!(res.is_present("define"));
+        #[cfg(feature = "serde")]
+        assert_eq!(res.to_json(), r#"{"define":{"a":"3","b":"2","c":"x=y","flag":"true"}}"#);
+
+        let res = parser.try_get_matches_from(["--verbose"]).unwrap();
+        assert_eq!(res.defines_of("define"), None);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().param("src"),
+            Arg::new().flag("verbose"),
+            Arg::new().param("dest"),
+        ]);
+
+        let err = parser.try_get_matches_from(Vec::<String>::new()).unwrap_err();
+        assert_eq!(err, ParseError::MissingRequired(vec![String::from("src"), String::from("dest")]));
+        assert_eq!(err.to_string(), "Invalid command, missing required arguments 'src', 'dest'");
+
+        // Partial input still reports the positional count
+        let res = parser.try_get_matches_from(["a"]);
+        assert_eq!(res, Err(ParseError::TooFewPositionals { expected: 2, got: 1 }));
+
+        // Without required args, empty input is fine
+        let parser = Parser::new();
+        parser.add_arg(Arg::new().flag("verbose"));
+        assert!(parser.try_get_matches_from(Vec::<String>::new()).unwrap().is_empty());
+
+        // A required option with a default is provided by it, with or without other tokens
+        parser.add_arg(Arg::new().input("config").required(true).default_with(|| String::from("app.toml")));
+        assert_eq!(parser.try_get_matches_from(Vec::<String>::new()).unwrap().value_of("config"), Some("app.toml"));
+        assert_eq!(parser.try_get_matches_from(["--verbose"]).unwrap().value_of("config"), Some("app.toml"));
+        parser.add_args(vec![Arg::new().param("file"), Arg::new().input("token").required(true)]);
+        let res = parser.try_get_matches_from(Vec::<String>::new());
+        assert_eq!(res, Err(ParseError::MissingRequired(vec![String::from("file"), String::from("token")])));
+    }
+
+    #[test]
+    fn test_namespace() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("db.host"),
+            Arg::new().input("db.port").short('p'),
+            Arg::new().flag("db.tls"),
+            Arg::new().input("dbx.user"),
+            Arg::new().input("cache.db.size"),
+        ]);
+        assert_eq!(parser.validate(), Ok(()));
+
+        let res = parser.try_get_matches_from(["--db.host", "localhost", "-p", "5432", "--db.tls", "--dbx.user", "me", "--cache.db.size", "1"]).unwrap();
+        assert_eq!(res.namespace("db"), HashMap::from([
+            (String::from("host"), String::from("localhost")),
+            (String::from("port"), String::from("5432")),
+            (String::from("tls"), String::from("true")),
+        ]));
+        assert_eq!(res.namespace("cache"), HashMap::from([(String::from("db.size"), String::from("1"))]));
+        assert!(res.namespace("log").is_empty());
+
+        parser.add_arg(Arg::new().input("cache.db"));
+        assert_eq!(parser.validate(), Err(ConfigError::NamespaceCollision(String::from("cache.db"))));
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize(r#"  run "a b" 'c "d"' e\ f "g\"h" x"y"z -- "--" '' "#).unwrap();
+        assert_eq!(tokens, vec![
+            (String::from("run"), false),
+            (String::from("a b"), true),
+            (String::from("c \"d\""), true),
+            (String::from("e f"), false),
+            (String::from("g\"h"), true),
+            (String::from("xyz"), true),
+            (String::from("--"), false),
+            (String::from("--"), true),
+            (String::from(""), true),
+        ]);
+
+        assert!(tokenize("").unwrap().is_empty());
+        assert_eq!(tokenize(r#"run "abc"#), Err(ParseError::Unexpected(String::from("\"abc"))));
+    }
+
+    #[test]
+    fn test_parse_str_quoted_terminator() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().param("cmd"),
+            Arg::new().param("sep"),
+            Arg::new().param("value"),
+            Arg::new().flag("verbose").short('v'),
+        ]);
+
+        // A quoted `--` is a positional literal
+        let res = parser.parse_str(r#"run "--" value"#).unwrap();
+        assert_eq!(res.value_of("cmd"), Some("run"));
+        assert_eq!(res.value_of("sep"), Some("--"));
+        assert_eq!(res.value_of("value"), Some("value"));
+
+        // An unquoted `--` terminates options
+        let res = parser.parse_str("run -- value");
+        assert_eq!(res, Err(ParseError::TooFewPositionals { expected: 3, got: 2 }));
+
+        let res = parser.parse_str("run -- -v value").unwrap();
+        assert_eq!(res.value_of("sep"), Some("-v"));
+        assert!(!res.is_present("verbose"));
+
+        parser.trailing_var_arg("rest");
+        let res = parser.parse_str(r#"run '--' value -- -v"#).unwrap();
+        assert_eq!(res.value_of("sep"), Some("--"));
+        assert_eq!(res.values_of("rest").unwrap(), ["-v"]);
+    }
+
+    #[test]
+    fn test_id() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("farbe").short('f').id("color"),
+            Arg::new().flag("ausfuehrlich").id("verbose").count(),
+            Arg::new().flag("alles").implies(&["verbose"]),
+            Arg::new().input("groesse").id("size").byte_size(),
+            Arg::new().param("datei").id("file"),
+        ]);
+        parser.with_defaults_from(HashMap::from([(String::from("size"), String::from("1KB"))]));
+        assert_eq!(parser.validate(), Ok(()));
+
+        let res = parser.try_get_matches_from(["-f", "rot", "--ausfuehrlich", "--ausfuehrlich", "a.txt"]).unwrap();
+        assert_eq!(res.value_of("color"), Some("rot"));
+        assert_eq!(res.count("verbose"), 2);
+        assert_eq!(res.byte_size_of("size"), Some(1000));
+        assert_eq!(res.value_of("file"), Some("a.txt"));
+        assert!(!res.is_present("farbe"));
+        assert!(!res.is_present("datei"));
+
+        let res = parser.try_get_matches_from(["--alles", "a.txt"]).unwrap();
+        assert!(res.is_present("verbose"));
+
+        // Errors still use the name the user typed
+        let res = parser.try_get_matches_from(["a.txt", "--groesse", "big"]);
+        assert!(matches!(res, Err(ParseError::Value { name, .. }) if name == "groesse"));
+    }
+
+    #[test]
+    fn test_parse_slice() {
+        let parser = Parser::new();
+        parser.add_args(vec![Arg::new().input("out").short('o'), Arg::new().param("file")]);
+
+        let args: Vec<String> = ["-o", "out.txt", "in.txt"].into_iter().map(String::from).collect();
+        let first = parser.parse_slice(&args).unwrap();
+        let second = parser.parse_slice(&args).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.value_of("out"), Some("out.txt"));
+        assert_eq!(args.len(), 3);
+
+        let res = parser.parse_slice(&args[..2]);
+        assert_eq!(res, Err(ParseError::TooFewPositionals { expected: 1, got: 0 }));
+    }
+
+    #[test]
+    fn test_require_params() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().param("src"),
+            Arg::new().param("dest"),
+            Arg::new().flag("verbose").short('v'),
+        ]);
+
+        let res = parser.try_get_matches_from(["a.txt", "-v"]);
+        assert_eq!(res, Err(ParseError::TooFewPositionals { expected: 2, got: 1 }));
+
+        parser.require_params(false);
+        let res = parser.try_get_matches_from(["a.txt", "-v"]).unwrap();
+        assert_eq!(res.value_of("src"), Some("a.txt"));
+        assert!(!res.is_present("dest"));
+        assert!(res.is_present("verbose"));
+
+        assert!(parser.try_get_matches_from(Vec::<String>::new()).unwrap().is_empty());
+        assert!(parser.missing_required(&[]).is_empty());
+
+        // Extra positionals are still an error
+        let res = parser.try_get_matches_from(["a", "b", "c"]);
+        assert_eq!(res, Err(ParseError::TooManyPositionals { expected: 2, got: 3 }));
+    }
+
+    #[test]
+    fn test_short_cluster() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("extract").short('x'),
+            Arg::new().flag("verbose").short('v').count(),
+            Arg::new().input("file").short('f'),
+            Arg::new().param("src"),
+        ]);
+
+        for cmd in [vec!["-xvf", "out", "in"], vec!["-xvfout", "in"], vec!["-xvf=out", "in"], vec!["in", "-xvf=out"]] {
+            let res = parser.try_get_matches_from(cmd).unwrap();
+            assert_eq!(res.value_of("file"), Some("out"));
+            assert!(res.is_present("extract"));
+            assert_eq!(res.count("verbose"), 1);
+            assert_eq!(res.value_of("src"), Some("in"));
+        }
+
+        // Everything after the value-taking short is its value
+        let res = parser.try_get_matches_from(["-vvfx=v", "in"]).unwrap();
+        assert_eq!(res.value_of("file"), Some("x=v"));
+        assert_eq!(res.count("verbose"), 2);
+
+        let res = parser.try_get_matches_from(["-xf=", "in"]).unwrap();
+        assert_eq!(res.value_of("file"), Some(""));
+
+        assert_eq!(parser.try_get_matches_from(["-xq", "in"]), Err(ParseError::Unexpected(String::from("-xq"))));
+        assert_eq!(parser.try_get_matches_from(["-xx", "in"]), Err(ParseError::Duplicate(String::from("-xx"))));
+        assert_eq!(parser.try_get_matches_from(["in", "-xvf"]), Err(ParseError::MissingValue(String::from("file"))));
+
+        let explained = parser.explain(["-xvf", "out", "in"]);
+        assert_eq!(explained[0].1, Classification::ShortOption('x'));
+        assert_eq!(explained[1].1, Classification::Value(String::from("file")));
+        assert_eq!(explained[2].1, Classification::Positional(String::from("src")));
+    }
 
     #[test]
-    fn create_arg() {
-        let default = Arg::new().input("default");
-        assert_eq!(default.name, "default");
-        assert_eq!(default.expecting, true);
-        
-        let short = Arg::new().input("short").short('s');
-        assert_eq!(short.name, "short");
-        if let ArgTypes::Short(c) = short.arg_type {
-            assert_eq!(c, 's');
-        } else {
-            assert!(false);
+    fn test_error_code() {
+        let errors = [
+            ParseError::Unexpected(String::from("-x")),
+            ParseError::Missing,
+            ParseError::Duplicate(String::from("-x")),
+            ParseError::OptionAfterPositional(String::from("-x")),
+            ParseError::TooManyPositionals { expected: 1, got: 2 },
+            ParseError::TooFewPositionals { expected: 2, got: 1 },
+            ParseError::MissingValue(String::from("out")),
+            ParseError::Value { name: String::from("size"), value: String::from("big"), kind: ValueErrorKind::Type("a byte size") },
+            ParseError::AmbiguousShort('x'),
+            ParseError::MissingRequired(vec![String::from("file")]),
+            ParseError::MissingRequirement { arg: String::from("upload"), requires: vec![String::from("token")] },
+            ParseError::TooManyArgs { limit: 64 },
+            ParseError::GroupViolation { members: vec![String::from("json")], reason: GroupViolationKind::TooMany },
+            ParseError::PartialGroup { group: vec![String::from("user")], present: vec![], missing: vec![String::from("user")] },
+            ParseError::InvalidMode { got: String::from("bulid"), allowed: vec![String::from("build")] },
+        ];
+        let codes: Vec<u8> = errors.iter().map(ParseError::code).collect();
+        assert_eq!(codes, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+    }
+
+    #[test]
+    fn test_into_arg() {
+        let arg = Arg::from("verbose");
+        assert_eq!(arg.name, "verbose");
+        assert!(matches!(arg.arg_type, ArgTypes::Flag));
+        assert_eq!(arg.short, None);
+
+        let arg = Arg::from(("quiet", 'q'));
+        assert_eq!(arg.name, "quiet");
+        assert!(matches!(arg.arg_type, ArgTypes::Flag));
+        assert_eq!(arg.short, Some('q'));
+
+        let parser = Parser::new();
+        parser.add_arg("verbose");
+        parser.add_arg(("quiet", 'q'));
+        parser.add_arg(Arg::new().input("out"));
+        parser.add_args(vec!["all", "dry-run"]);
+        parser.add_args(vec![("force", 'f'), ("recursive", 'r')]);
+        assert_eq!(parser.len(), 7);
+
+        let res = parser.try_get_matches_from(["--verbose", "-q", "--dry-run", "-rf", "--out", "a.txt"]).unwrap();
+        assert_eq!(res.len(), 6);
+        assert!(res.is_present("force"));
+        assert!(!res.is_present("all"));
+    }
+
+    #[test]
+    fn test_build() {
+        let spec = |name: &str, kind, short, required| ArgSpec { name: String::from(name), kind, short, required };
+
+        let param = Arg::build(spec("file", ArgKind::Param, None, true));
+        assert!(matches!(param.arg_type, ArgTypes::Param(false)));
+        assert!(!param.expecting);
+
+        let input = Arg::build(spec("out", ArgKind::Input, Some('o'), true));
+        assert!(matches!(input.arg_type, ArgTypes::Input));
+        assert!(input.expecting);
+        assert!(input.required);
+        assert_eq!(input.short, Some('o'));
+
+        let flag = Arg::build(spec("verbose", ArgKind::Flag, Some('v'), false));
+        assert!(matches!(flag.arg_type, ArgTypes::Flag));
+        assert!(!flag.expecting);
+        assert!(!flag.required);
+
+        let parser = Parser::new();
+        parser.add_args(vec![param, input, flag]);
+        assert_eq!(parser.missing_required(&["file"]), vec!["out"]);
+
+        let res = parser.try_get_matches_from(["-v", "-o", "a.txt", "b.txt"]).unwrap();
+        assert_eq!(res.value_of("out"), Some("a.txt"));
+        assert_eq!(res.value_of("file"), Some("b.txt"));
+
+        let res = parser.try_get_matches_from(["-v", "b.txt"]);
+        assert_eq!(res, Err(ParseError::MissingRequired(vec![String::from("out")])));
+
+        let res = parser.try_get_matches_from(Vec::<String>::new());
+        assert_eq!(res, Err(ParseError::MissingRequired(vec![String::from("file"), String::from("out")])));
+    }
+
+    #[test]
+    fn test_parse_with_warnings() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("default").short('d'),
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().param("p1"),
+            Arg::new().param("p2"),
+        ]);
+
+        // Missing params are an error before any warning can be given
+        let res = parser.parse_with_warnings(["--default", "valueA", "param1"]);
+        assert_eq!(res, Err(ParseError::TooFewPositionals { expected: 2, got: 1 }));
+
+        parser.require_params(false);
+        let res = parser.parse_with_warnings(["--default", "valueA", "param1"]).unwrap();
+        assert_eq!(res.value_of("default"), Some("valueA"));
+        assert_eq!(res.warnings(), [ParseWarning::ValueMayBePositional {
+            arg: String::from("default"),
+            value: String::from("valueA"),
+        }]);
+        assert_eq!(res.warnings()[0].to_string(), "'valueA' was taken as the value of 'default', but a positional argument is missing");
+
+        // No warning when every param is filled, the value is attached, or another option follows
+        assert!(parser.parse_with_warnings(["-d", "valueA", "param1", "param2"]).unwrap().warnings().is_empty());
+        assert!(parser.parse_with_warnings(["-d=valueA", "param1"]).unwrap().warnings().is_empty());
+        assert!(parser.parse_with_warnings(["-d", "valueA", "-v", "param1"]).unwrap().warnings().is_empty());
+
+        // Regular parsing never collects warnings
+        assert!(parser.try_get_matches_from(["--default", "valueA", "param1"]).unwrap().warnings().is_empty());
+    }
+
+    #[test]
+    fn test_values_until_terminator() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("files").short('f').values_until_terminator(),
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().param("cmd"),
+        ]);
+
+        // Terminated by `--`
+        let res = parser.try_get_matches_from(["--files", "a", "-v", "b", "--", "rm", "-v"]).unwrap();
+        assert_eq!(res.values_of("files").unwrap(), ["a", "-v", "b"]);
+        assert_eq!(res.value_of("cmd"), Some("rm"));
+        assert!(res.is_present("verbose"));
+
+        // Terminated by the end of the input
+        let res = parser.try_get_matches_from(["rm", "-f", "a", "--verbose"]).unwrap();
+        assert_eq!(res.values_of("files").unwrap(), ["a", "--verbose"]);
+        assert!(!res.is_present("verbose"));
+
+        let res = parser.try_get_matches_from(["rm", "-f=a", "b", "--"]).unwrap();
+        assert_eq!(res.values_of("files").unwrap(), ["a", "b"]);
+
+        let res = parser.parse_str(r#"rm -f a "--" b"#).unwrap();
+        assert_eq!(res.values_of("files").unwrap(), ["a", "--", "b"]);
+
+        let res = parser.try_get_matches_from(["rm", "-f"]).unwrap();
+        assert!(res.values_of("files").unwrap().is_empty());
+
+        let res = parser.try_get_matches_from(["-vf", "a", "b", "--", "rm"]).unwrap();
+        assert_eq!(res.values_of("files").unwrap(), ["a", "b"]);
+        assert!(res.is_present("verbose"));
+
+        // Giving the input again follows the duplicate policy like any other input
+        let res = parser.try_get_matches_from(["--files", "a", "--", "--files", "b", "--", "rm"]);
+        assert_eq!(res, Err(ParseError::Duplicate(String::from("--files"))));
+        let res = parser.try_get_matches_from(["rm", "-f=a", "b", "--", "-f", "--"]);
+        assert_eq!(res, Err(ParseError::Duplicate(String::from("-f"))));
+        parser.on_duplicate(DuplicatePolicy::LastWins);
+        let res = parser.try_get_matches_from(["--files", "a", "--", "--files", "b", "--", "rm"]).unwrap();
+        assert_eq!(res.values_of("files").unwrap(), ["b"]);
+        parser.on_duplicate(DuplicatePolicy::Error);
+
+        let explained = parser.explain(["-f", "a", "-v", "--", "rm"]);
+        let classifications: Vec<Classification> = explained.into_iter().map(|(_, c)| c).collect();
+        assert_eq!(classifications, vec![
+            Classification::ShortOption('f'),
+            Classification::Value(String::from("files")),
+            Classification::Value(String::from("files")),
+            Classification::Terminator,
+            Classification::Positional(String::from("cmd")),
+        ]);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut param = Arg::new().param("file");
+        param.set_used(true);
+        assert!(matches!(param.arg_type, ArgTypes::Param(true)));
+        param.reset();
+        assert!(matches!(param.arg_type, ArgTypes::Param(false)));
+        param.set_used(true);
+        assert!(matches!(param.arg_type, ArgTypes::Param(true)));
+
+        // Other kinds are left alone
+        let mut flag = Arg::new().flag("verbose");
+        flag.reset();
+        assert!(matches!(flag.arg_type, ArgTypes::Flag));
+    }
+
+    #[test]
+    fn test_catch_all() {
+        let parser = Parser::new();
+        parser.add_args(vec![Arg::new().flag("verbose").short('v'), Arg::new().param("file")]);
+
+        // Unknown options are errors by default
+        let res = parser.try_get_matches_from(["--color", "a.txt"]);
+        assert_eq!(res, Err(ParseError::Unexpected(String::from("--color"))));
+
+        parser.catch_all("extras");
+        let res = parser.try_get_matches_from(["--color", "red", "--fast", "-v", "--size=10", "a.txt", "--last"]).unwrap();
+        assert_eq!(res.extras(), HashMap::from([
+            (String::from("color"), String::from("red")),
+            (String::from("fast"), String::from("true")),
+            (String::from("size"), String::from("10")),
+            (String::from("last"), String::from("true")),
+        ]));
+        assert!(res.is_present("extras"));
+        assert!(res.is_present("verbose"));
+        assert_eq!(res.value_of("file"), Some("a.txt"));
+
+        parser.catch_all_mode(CatchAllMode::Flag);
+        let res = parser.try_get_matches_from(["--color", "red", "--size=10"]).unwrap();
+        assert_eq!(res.extras(), HashMap::from([
+            (String::from("color"), String::from("true")),
+            (String::from("size"), String::from("10")),
+        ]));
+        assert_eq!(res.value_of("file"), Some("red"));
+
+        // Unknown shorts are still errors
+        let res = parser.try_get_matches_from(["-x", "a.txt"]);
+        assert_eq!(res, Err(ParseError::Unexpected(String::from("-x"))));
+
+        let res = parser.try_get_matches_from(["a.txt"]).unwrap();
+        assert!(res.extras().is_empty());
+    }
+
+    #[test]
+    fn test_error_formatter() {
+        let parser = Parser::new();
+        parser.add_arg(Arg::new().param("datei"));
+
+        let err = parser.try_get_matches_from(["a", "b"]).unwrap_err();
+        assert_eq!(parser.format_error(&err), err.to_string());
+
+        parser.error_formatter(Box::new(|err: &ParseError| match err {
+            ParseError::TooManyPositionals { expected, got } => format!("Zu viele Argumente: {} erwartet, {} erhalten", expected, got),
+            err => format!("Fehler {}", err.code()),
+        }));
+        assert_eq!(parser.format_error(&err), "Zu viele Argumente: 1 erwartet, 2 erhalten");
+        assert_eq!(parser.format_error(&ParseError::Missing), "Fehler 2");
+
+        // Merged parsers keep their own formatter
+        let other = Parser::new();
+        other.error_formatter(|_| String::from("other"));
+        parser.merge(&other).unwrap();
+        assert_eq!(parser.format_error(&ParseError::Missing), "Fehler 2");
+    }
+
+    #[test]
+    fn test_usage_groups() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("input").short('i').group_label("Input Options"),
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().input("output").short('o').group_label("Output Options"),
+            Arg::new().param("file"),
+            Arg::new().input("encoding").group_label("Input Options"),
+            Arg::new().input("define").short('D').defines(),
+            Arg::new().input("files").values_until_terminator().group_label("Input Options"),
+        ]);
+
+        assert_eq!(parser.usage(), [
+            "Input Options:",
+            "  -i, --input <input>",
+            "      --encoding <encoding>",
+            "      --files <files>... --",
+            "",
+            "Options:",
+            "  -v, --verbose",
+            "  -D, --define <key=value>",
+            "",
+            "Output Options:",
+            "  -o, --output <output>",
+            "",
+            "Arguments:",
+            "  <file>",
+            "",
+        ].join("\n"));
+
+        assert_eq!(Parser::new().usage(), "");
+    }
+
+    #[test]
+    fn test_parse_interspersed() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("force").short('f'),
+            Arg::new().flag("other"),
+            Arg::new().param("pos"),
+        ]);
+
+        let res = parser.try_get_matches_from(["-f", "pos", "--other"]).unwrap();
+        assert!(res.is_present("force"));
+        assert!(res.is_present("other"));
+        assert_eq!(res.value_of("pos"), Some("pos"));
+
+        parser.parse_interspersed(false);
+        let res = parser.try_get_matches_from(["-f", "pos", "--other"]);
+        assert_eq!(res, Err(ParseError::TooManyPositionals { expected: 1, got: 2 }));
+
+        parser.add_arg(Arg::new().param("rest"));
+        let res = parser.try_get_matches_from(["-f", "pos", "--other"]).unwrap();
+        assert!(res.is_present("force"));
+        assert!(!res.is_present("other"));
+        assert_eq!(res.value_of("rest"), Some("--other"));
+
+        // A `--` after the first positional is positional too
+        let res = parser.try_get_matches_from(["pos", "--"]).unwrap();
+        assert_eq!(res.value_of("rest"), Some("--"));
+
+        let explained = parser.explain(["-f", "pos", "--other"]);
+        assert_eq!(explained[0].1, Classification::ShortOption('f'));
+        assert_eq!(explained[2].1, Classification::Positional(String::from("rest")));
+    }
+
+    #[test]
+    fn test_auto_short() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("verbose").auto_short(),
+            Arg::new().flag("version").auto_short(),
+            Arg::new().input("output").short('x').auto_short(),
+            Arg::new().input("out-dir").auto_short(),
+        ]);
+        parser.add_arg(Arg::new().flag("quiet").auto_short());
+
+        let shorts: Vec<Option<char>> = parser.args().into_iter().map(|arg| arg.short).collect();
+        assert_eq!(shorts, vec![Some('v'), None, Some('x'), Some('o'), Some('q')]);
+
+        let res = parser.try_get_matches_from(["-v", "--version", "-o", "dir", "-q"]).unwrap();
+        assert!(res.is_present("verbose"));
+        assert_eq!(res.value_of("out-dir"), Some("dir"));
+    }
+
+    #[test]
+    fn test_input_before_terminator() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("out").short('o'),
+            Arg::new().input("raw").take_next_raw(),
+            Arg::new().param("file"),
+        ]);
+
+        let res = parser.try_get_matches_from(["--out", "--", "a.txt"]);
+        assert_eq!(res, Err(ParseError::MissingValue(String::from("out"))));
+
+        let res = parser.try_get_matches_from(["a.txt", "-o", "--"]);
+        assert_eq!(res, Err(ParseError::MissingValue(String::from("out"))));
+
+        // Also reported ahead of the missing positional, which the terminator was likely meant to follow
+        let res = parser.try_get_matches_from(["--out", "--"]);
+        assert_eq!(res, Err(ParseError::MissingValue(String::from("out"))));
+        let res = parser.try_get_matches_from(["-o"]);
+        assert_eq!(res, Err(ParseError::MissingValue(String::from("out"))));
+        let res = parser.try_get_matches_from(["--raw", "--out", "--"]);
+        assert_eq!(res, Err(ParseError::TooFewPositionals { expected: 1, got: 0 }));
+
+        // Raw inputs and quoted terminators are values
+        let res = parser.try_get_matches_from(["--raw", "--", "a.txt"]).unwrap();
+        assert_eq!(res.value_of("raw"), Some("--"));
+
+        let res = parser.parse_str(r#"--out "--" a.txt"#).unwrap();
+        assert_eq!(res.value_of("out"), Some("--"));
+    }
+
+    #[test]
+    fn test_complete() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("verbose").short('v').alias("loud"),
+            Arg::new().flag("version").short('V'),
+            Arg::new().input("out").short('o'),
+            Arg::new().param("file"),
+        ]);
+
+        assert_eq!(parser.complete("--ve"), vec!["--verbose", "--version"]);
+        assert_eq!(parser.complete("--verb"), vec!["--verbose"]);
+        assert_eq!(parser.complete("--l"), vec!["--loud"]);
+        assert_eq!(parser.complete("--"), vec!["--verbose", "--loud", "--version", "--out"]);
+        assert!(parser.complete("--fi").is_empty());
+
+        assert_eq!(parser.complete("-"), vec!["-v", "-V", "-o"]);
+        assert_eq!(parser.complete("-o"), vec!["-o"]);
+        assert!(parser.complete("-x").is_empty());
+
+        assert!(parser.complete("fi").is_empty());
+    }
+
+    #[test]
+    fn test_non_empty() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("name").short('n').non_empty(),
+            Arg::new().input("title").short('t'),
+        ]);
+
+        let err = ParseError::Value { name: String::from("name"), value: String::new(), kind: ValueErrorKind::Empty };
+        assert_eq!(parser.try_get_matches_from(["-n="]), Err(err.clone()));
+        assert_eq!(parser.parse_str(r#"--name """#), Err(err.clone()));
+        assert_eq!(parser.parse_str("--name ''"), Err(err.clone()));
+        assert_eq!(err.to_string(), "Invalid command, invalid value '' for 'name': the value can't be empty");
+
+        let res = parser.parse_str("--name x -t=").unwrap();
+        assert_eq!(res.value_of("name"), Some("x"));
+        assert_eq!(res.value_of("title"), Some(""));
+    }
+
+    #[test]
+    fn test_usage_width() {
+        assert_eq!(wrap("a bb ccc dddd", 6), vec!["a bb", "ccc", "dddd"]);
+        assert_eq!(wrap("unbreakable word", 4), vec!["unbreakable", "word"]);
+        assert_eq!(wrap("", 10), vec![""]);
+
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("output").short('o').help("Write the result to this file instead of printing it"),
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().param("file").help("File to read"),
+        ]);
+        parser.usage_width(40);
+
+        assert_eq!(parser.usage(), [
+            "Options:",
+            "  -o, --output <output>  Write the",
+            "                         result to this",
+            "                         file instead of",
+            "                         printing it",
+            "  -v, --verbose",
+            "",
+            "Arguments:",
+            "  <file>                 File to read",
+            "",
+        ].join("\n"));
+
+        parser.usage_width(80);
+        assert!(parser.usage().contains("  -o, --output <output>  Write the result to this file instead of printing it\n"));
+    }
+
+    #[test]
+    fn test_takes_positionals() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("point").short('p').takes_positionals(2),
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().param("file"),
+        ]);
+
+        let res = parser.try_get_matches_from(["in.svg", "--point", "3", "4", "-v"]).unwrap();
+        assert_eq!(res.group_of("point"), ["3", "4"]);
+        assert_eq!(res.value_of("file"), Some("in.svg"));
+        assert!(res.is_present("point"));
+        assert!(res.is_present("verbose"));
+
+        let res = parser.try_get_matches_from(["-vp", "-1", "2", "in.svg"]).unwrap();
+        assert_eq!(res.group_of("point"), ["-1", "2"]);
+
+        // The group is one argument, however many values it holds
+        let res = parser.try_get_matches_from(["--point", "1", "2", "in.svg"]).unwrap();
+        assert_eq!(res.len(), 2);
+        #[cfg(feature = "serde")]
+        assert_eq!(res.to_json(), r#"{"file":"in.svg","point":["1","2"]}"#);
+
+        // The group can't be cut short
+        let res = parser.try_get_matches_from(["in.svg", "--point", "3", "-v"]);
+        assert_eq!(res, Err(ParseError::MissingValue(String::from("point"))));
+        let res = parser.try_get_matches_from(["in.svg", "--point", "3"]);
+        assert_eq!(res, Err(ParseError::MissingValue(String::from("point"))));
+        let res = parser.try_get_matches_from(["--point", "3", "--", "in.svg"]);
+        assert_eq!(res, Err(ParseError::MissingValue(String::from("point"))));
+        let res = parser.parse_str(r#"--point 3 "--" in.svg"#).unwrap();
+        assert_eq!(res.group_of("point"), ["3", "--"]);
+
+        // Grouped tokens are not positionals
+        let res = parser.try_get_matches_from(["--point", "3", "4"]);
+        assert_eq!(res, Err(ParseError::TooFewPositionals { expected: 1, got: 0 }));
+        let res = parser.try_get_matches_from(["--point", "3", "4", "a", "b"]);
+        assert_eq!(res, Err(ParseError::TooManyPositionals { expected: 1, got: 2 }));
+        let res = parser.try_get_matches_from(["--point=3", "in.svg"]);
+        assert_eq!(res, Err(ParseError::Unexpected(String::from("--point=3"))));
+
+        let res = parser.try_get_matches_from(["--point", "1", "2", "--point", "3", "4", "in.svg"]);
+        assert_eq!(res, Err(ParseError::Duplicate(String::from("--point"))));
+        parser.on_duplicate(DuplicatePolicy::LastWins);
+        let res = parser.try_get_matches_from(["--point", "1", "2", "--point", "3", "4", "in.svg"]).unwrap();
+        assert_eq!(res.group_of("point"), ["3", "4"]);
+        assert!(res.group_of("verbose").is_empty());
+
+        let explained = parser.explain(["--point", "3", "4", "in.svg"]);
+        assert_eq!(explained[1].1, Classification::Value(String::from("point")));
+        assert_eq!(explained[2].1, Classification::Value(String::from("point")));
+        assert_eq!(explained[3].1, Classification::Positional(String::from("file")));
+        assert!(parser.usage().contains("  -p, --point <point> <point>\n"));
+    }
+
+    #[test]
+    fn test_get_flag() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("color").short('c'),
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().input("out").short('o'),
+            Arg::new().param("file"),
+        ]);
+
+        let res = parser.try_get_matches_from(["--color", "a.txt"]).unwrap();
+        assert!(res.get_flag("color"));
+        assert!(!res.get_flag("verbose"));
+        assert!(!res.get_flag("out"));
+        assert!(res.get_flag("file"));
+
+        parser.plus_flags(true);
+        let res = parser.try_get_matches_from(["+c", "-verbose", "--out", "b.txt", "a.txt"]).unwrap();
+        assert!(res.get_flag("color"));
+        assert!(res.is_present("verbose"));
+        assert!(!res.get_flag("verbose"));
+        assert!(res.get_flag("out"));
+    }
+
+    #[test]
+    fn test_long_match() {
+        assert_eq!(attached_value("--out"), None);
+        assert_eq!(attached_value("--out=a=b"), Some("a=b"));
+        assert_eq!(attached_value("--out="), Some(""));
+        assert_eq!(attached_value("-o=a"), Some("a"));
+
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("out").alias("output"),
+            Arg::new().flag("verbose"),
+            Arg::new().input("define").defines(),
+        ]);
+
+        let res = parser.try_get_matches_from(["--out", "a.txt", "--verbose"]).unwrap();
+        assert_eq!(res.value_of("out"), Some("a.txt"));
+        let res = parser.try_get_matches_from(["--out=a.txt"]).unwrap();
+        assert_eq!(res.value_of("out"), Some("a.txt"));
+        let res = parser.try_get_matches_from(["--output=a=b", "--define=key=value"]).unwrap();
+        assert_eq!(res.value_of("out"), Some("a=b"));
+        assert_eq!(res.defines_of("define").unwrap()["key"], "value");
+
+        // Names must match exactly, not just by prefix or suffix
+        for token in ["--ou=a.txt", "--outt=a.txt", "--tout", "--verbose=true", "--=a.txt", "--="] {
+            assert_eq!(parser.try_get_matches_from([token]), Err(ParseError::Unexpected(String::from(token))));
+        }
+    }
+
+    #[test]
+    fn test_on_token() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("out").short('o'),
+            Arg::new().flag("verbose"),
+            Arg::new().param("file"),
+        ]);
+        let expected = parser.try_get_matches_from(["-o", "a.txt", "--verbose", "b.txt"]);
+
+        let seen = Rc::new(RefCell::new(vec![]));
+        let log = Rc::clone(&seen);
+        parser.on_token(move |token, classification| log.borrow_mut().push((String::from(token), classification)));
+
+        assert_eq!(parser.try_get_matches_from(["-o", "a.txt", "--verbose", "b.txt"]), expected);
+        assert_eq!(*seen.borrow(), [
+            (String::from("-o"), Classification::ShortOption('o')),
+            (String::from("a.txt"), Classification::Value(String::from("out"))),
+            (String::from("--verbose"), Classification::LongOption(String::from("verbose"))),
+            (String::from("b.txt"), Classification::Positional(String::from("file"))),
+        ]);
+
+        seen.borrow_mut().clear();
+        assert!(parser.try_get_matches_from(["--nope", "b.txt"]).is_err());
+        assert_eq!(*seen.borrow(), [(String::from("--nope"), Classification::Unknown)]);
+
+        // The classification is what the parse did with the token, even where quoting, the mode or a catch-all matter
+        let parser = Parser::new();
+        parser.add_args(vec![Arg::new().input("val"), Arg::new().param("cmd")]);
+        parser.catch_all("extra");
+        parser.mode(&["build"]).unwrap();
+        let log = Rc::clone(&seen);
+        parser.on_token(move |token, classification| log.borrow_mut().push((String::from(token), classification)));
+
+        seen.borrow_mut().clear();
+        let output = parser.parse_str(r#"build --color red --val "--" run"#).unwrap();
+        assert_eq!(output.value_of("val"), Some("--"));
+        assert_eq!(*seen.borrow(), [
+            (String::from("build"), Classification::Mode),
+            (String::from("--color"), Classification::LongOption(String::from("extra"))),
+            (String::from("red"), Classification::Value(String::from("extra"))),
+            (String::from("--val"), Classification::LongOption(String::from("val"))),
+            (String::from("--"), Classification::Value(String::from("val"))),
+            (String::from("run"), Classification::Positional(String::from("cmd"))),
+        ]);
+
+        // A callback can parse again without panicking
+        let nested = Rc::new(parser.clone());
+        let inner = Rc::downgrade(&nested);
+        nested.on_token(move |_, _| {
+            let inner = inner.upgrade().unwrap();
+            assert!(inner.try_get_matches_from(["build", "run"]).is_ok());
+        });
+        assert!(nested.try_get_matches_from(["build", "run"]).is_ok());
+    }
+
+    #[test]
+    fn test_requires_any() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("upload").requires_any(&["token", "password"]),
+            Arg::new().input("token"),
+            Arg::new().input("password").default_with(|| String::from("hunter2")),
+            Arg::new().flag("ci").implies(&["token"]),
+        ]);
+
+        let err = parser.try_get_matches_from(["--upload"]).unwrap_err();
+        assert_eq!(err, ParseError::MissingRequirement {
+            arg: String::from("upload"),
+            requires: vec![String::from("token"), String::from("password")],
+        });
+        assert_eq!(err.to_string(), "Invalid command, 'upload' requires one of 'token', 'password'");
+
+        assert!(parser.try_get_matches_from(["--upload", "--password", "abc"]).is_ok());
+        assert!(parser.try_get_matches_from(["--upload", "--token", "abc", "--password", "abc"]).is_ok());
+        assert!(parser.try_get_matches_from(["--upload", "--ci"]).is_ok());
+        assert!(parser.try_get_matches_from(["--token", "abc"]).is_ok());
+
+        assert_eq!(parser.validate(), Ok(()));
+        parser.add_arg(Arg::new().flag("download").requires_any(&["tokn"]));
+        assert_eq!(parser.validate(), Err(ConfigError::UnknownReference {
+            arg: String::from("download"),
+            reference: String::from("tokn"),
+        }));
+    }
+
+    #[test]
+    fn test_required_unless() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("config").required_unless("no-config").required_unless("defaults"),
+            Arg::new().flag("no-config"),
+            Arg::new().flag("defaults"),
+            Arg::new().flag("verbose"),
+        ]);
+
+        let res = parser.try_get_matches_from(["--verbose"]);
+        assert_eq!(res, Err(ParseError::MissingRequired(vec![String::from("config")])));
+        assert!(parser.try_get_matches_from(["--no-config"]).is_ok());
+        assert!(parser.try_get_matches_from(["--defaults", "--verbose"]).is_ok());
+        assert!(parser.try_get_matches_from(["--config", "app.toml"]).is_ok());
+
+        assert_eq!(parser.missing_required(&[]), vec!["config"]);
+        assert!(parser.missing_required(&["no-config"]).is_empty());
+
+        assert_eq!(parser.validate(), Ok(()));
+        parser.add_arg(Arg::new().input("user").required_unless("pasword"));
+        assert_eq!(parser.validate(), Err(ConfigError::UnknownReference {
+            arg: String::from("user"),
+            reference: String::from("pasword"),
+        }));
+    }
+
+    #[test]
+    fn test_define_schema() {
+        let parser = Parser::new();
+        parser.add_arg(Arg::new().input("env").short('e').define_schema(&[
+            ("PORT", DefineKind::Int),
+            ("DEBUG", DefineKind::Bool),
+            ("NAME", DefineKind::Text),
+        ]));
+
+        let res = parser.try_get_matches_from(["-e", "PORT=-08", "-e", "DEBUG", "-e", "NAME=42", "-e", "USER=me"]).unwrap();
+        let env = res.defines_of("env").unwrap();
+        assert_eq!(env["PORT"], "-8");
+        assert_eq!(env["DEBUG"], "true");
+        assert_eq!(env["NAME"], "42");
+        assert_eq!(env["USER"], "me");
+
+        let res = parser.try_get_matches_from(["-e", "PORT=http"]);
+        assert_eq!(res, Err(ParseError::Value {
+            name: String::from("env"),
+            value: String::from("PORT=http"),
+            kind: ValueErrorKind::Type("an integer"),
+        }));
+        let res = parser.try_get_matches_from(["-e", "DEBUG=yes"]);
+        assert_eq!(res, Err(ParseError::Value {
+            name: String::from("env"),
+            value: String::from("DEBUG=yes"),
+            kind: ValueErrorKind::Type("a boolean"),
+        }));
+        let res = parser.try_get_matches_from(["-e", "PORT"]);
+        assert!(matches!(res, Err(ParseError::Value { kind: ValueErrorKind::Type("an integer"), .. })));
+
+        let parser = Parser::new();
+        parser.add_arg(Arg::new().input("env").define_schema(&[("PORT", DefineKind::Int)]).deny_unknown_keys());
+        assert!(parser.try_get_matches_from(["--env", "PORT=1"]).is_ok());
+        let err = parser.try_get_matches_from(["--env=USER=me"]).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid command, invalid value 'USER=me' for 'env': the key is not known");
+    }
+
+    #[test]
+    fn test_before_after_help() {
+        let parser = Parser::new();
+        parser.add_args(vec![Arg::new().param("file"), Arg::new().flag("verbose").short('v')]);
+        parser.before_help("Prints a file.");
+        parser.after_help("Examples:\n  cat -v notes.txt");
+
+        assert_eq!(parser.usage(), [
+            "Prints a file.",
+            "",
+            "Arguments:",
+            "  <file>",
+            "",
+            "Options:",
+            "  -v, --verbose",
+            "",
+            "Examples:",
+            "  cat -v notes.txt",
+            "",
+        ].join("\n"));
+
+        let other = Parser::new();
+        other.before_help("Other.");
+        other.after_help("Other notes.");
+        parser.merge(&other).unwrap();
+        assert!(parser.usage().starts_with("Prints a file.\n"));
+        assert!(parser.usage().ends_with("cat -v notes.txt\n"));
+    }
+
+    #[test]
+    fn test_parse_map() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().param("file"),
+            Arg::new().input("size").byte_size(),
+            Arg::new().input("mode").default_with(|| String::from("fast")),
+            Arg::new().input("token").required(true),
+            Arg::new().flag("verbose").implies(&["color"]),
+            Arg::new().flag("color"),
+            Arg::new().flag("point").takes_positionals(2),
+        ]);
+        let map = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(k, v)| (String::from(*k), String::from(*v))).collect()
+        };
+
+        let res = parser.parse_map(&map(&[
+            ("file", "a.txt"),
+            ("size", "1KB"),
+            ("token", "abc"),
+            ("verbose", "true"),
+            ("point", "3 4"),
+        ])).unwrap();
+        assert_eq!(res.value_of("file"), Some("a.txt"));
+        assert_eq!(res.byte_size_of("size"), Some(1000));
+        assert_eq!(res.value_of("mode"), Some("fast"));
+        assert!(res.is_present("color"));
+        assert_eq!(res.group_of("point"), ["3", "4"]);
+
+        let res = parser.parse_map(&map(&[("file", "a.txt"), ("token", "abc"), ("verbose", "false")])).unwrap();
+        assert!(!res.is_present("verbose"));
+        assert!(!res.is_present("color"));
+
+        // Missing and invalid values
+        let res = parser.parse_map(&map(&[("token", "abc")]));
+        assert_eq!(res, Err(ParseError::MissingRequired(vec![String::from("file")])));
+        let res = parser.parse_map(&map(&[("file", "a.txt")]));
+        assert_eq!(res, Err(ParseError::MissingRequired(vec![String::from("token")])));
+        let res = parser.parse_map(&map(&[("file", "a.txt"), ("token", "abc"), ("size", "huge")]));
+        assert!(matches!(res, Err(ParseError::Value { kind: ValueErrorKind::Type("a byte size"), .. })));
+        let res = parser.parse_map(&map(&[("file", "a.txt"), ("token", "abc"), ("verbose", "yes")]));
+        assert!(matches!(res, Err(ParseError::Value { kind: ValueErrorKind::Type("a boolean"), .. })));
+        let res = parser.parse_map(&map(&[("file", "a.txt"), ("token", "abc"), ("point", "3")]));
+        assert_eq!(res, Err(ParseError::MissingValue(String::from("point"))));
+        let res = parser.parse_map(&map(&[("file", "a.txt"), ("token", "abc"), ("nope", "1")]));
+        assert_eq!(res, Err(ParseError::Unexpected(String::from("nope"))));
+    }
+
+    #[test]
+    fn test_disables_validation() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().param("src"),
+            Arg::new().param("dest"),
+            Arg::new().input("token").required(true),
+            Arg::new().flag("upload").short('u').requires_any(&["token"]),
+            Arg::new().input("size").byte_size(),
+            Arg::new().flag("force").short('f').disables_validation(),
+        ]);
+
+        let res = parser.try_get_matches_from(["a.txt"]);
+        assert_eq!(res, Err(ParseError::TooFewPositionals { expected: 2, got: 1 }));
+        let res = parser.try_get_matches_from(["a.txt", "--upload", "--force"]).unwrap();
+        assert_eq!(res.value_of("src"), Some("a.txt"));
+        assert!(!res.is_present("dest"));
+        assert!(parser.try_get_matches_from(["-f"]).is_ok());
+
+        // Every way of calling the flag skips validation, unless it's disabled
+        let res = parser.try_get_matches_from(["-uf"]).unwrap();
+        assert!(res.is_present("upload"));
+        parser.plus_flags(true);
+        assert!(parser.try_get_matches_from(["+force"]).is_ok());
+        let res = parser.try_get_matches_from(["-force"]);
+        assert_eq!(res, Err(ParseError::TooFewPositionals { expected: 2, got: 0 }));
+        parser.plus_flags(false);
+
+        // Syntax and values are still checked
+        let res = parser.try_get_matches_from(["--force", "--nope"]);
+        assert_eq!(res, Err(ParseError::Unexpected(String::from("--nope"))));
+        let res = parser.try_get_matches_from(["--force", "a", "b", "c"]);
+        assert_eq!(res, Err(ParseError::TooManyPositionals { expected: 2, got: 3 }));
+        let res = parser.try_get_matches_from(["--force", "--size", "huge"]);
+        assert!(matches!(res, Err(ParseError::Value { .. })));
+
+        let map = HashMap::from([(String::from("force"), String::from("true"))]);
+        assert!(parser.parse_map(&map).is_ok());
+    }
+
+    #[test]
+    fn test_clone_parser() {
+        let parser = Parser::new();
+        parser.add_args(vec![Arg::new().param("file"), Arg::new().flag("verbose")]);
+        parser.plus_flags(true);
+
+        let variant = parser.clone();
+        variant.add_arg(Arg::new().input("out"));
+        variant.plus_flags(false);
+        variant.before_help("Variant.");
+
+        assert_eq!(parser.args().len(), 2);
+        assert_eq!(variant.args().len(), 3);
+        assert!(parser.try_get_matches_from(["a.txt", "--out", "b.txt"]).is_err());
+        assert!(variant.try_get_matches_from(["a.txt", "--out", "b.txt"]).is_ok());
+        assert!(parser.try_get_matches_from(["a.txt", "+verbose"]).is_ok());
+        assert!(variant.try_get_matches_from(["a.txt", "+verbose"]).is_err());
+        assert!(!parser.usage().contains("Variant."));
+    }
+
+    #[test]
+    fn test_positionals_filled() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().param("first"),
+            Arg::new().param("second").id("2nd"),
+            Arg::new().param("third"),
+            Arg::new().flag("number").short('n'),
+        ]);
+
+        let res = parser.try_get_matches_from(["a.txt", "-n", "b.txt", "c.txt"]).unwrap();
+        assert_eq!(res.positional_count(), 3);
+        assert_eq!(res.positional_names(), ["first", "2nd", "third"]);
+
+        parser.require_params(false);
+        let res = parser.try_get_matches_from(["-n"]).unwrap();
+        assert_eq!(res.positional_count(), 0);
+        assert!(res.positional_names().is_empty());
+    }
+
+    #[test]
+    fn test_value_delimiter() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("features").short('F').value_delimiter(','),
+            Arg::new().input("sizes").value_delimiter(':').byte_size(),
+            Arg::new().input("columns").value_delimiter(',').keep_empty_values(),
+        ]);
+
+        let res = parser.try_get_matches_from(["--features", "a,b,c"]).unwrap();
+        assert_eq!(res.values_of("features").unwrap(), ["a", "b", "c"]);
+        assert!(res.is_present("features"));
+        assert_eq!(res.len(), 1);
+        #[cfg(feature = "serde")]
+        assert_eq!(res.to_json(), r#"{"features":["a","b","c"]}"#);
+        let res = parser.try_get_matches_from(["-F=a,,b,", "--sizes=1KB:2KB"]).unwrap();
+        assert_eq!(res.values_of("features").unwrap(), ["a", "b"]);
+        assert_eq!(res.values_of("sizes").unwrap(), ["1000", "2000"]);
+        let res = parser.try_get_matches_from(["--features", ","]).unwrap();
+        assert!(res.values_of("features").unwrap().is_empty());
+
+        let res = parser.try_get_matches_from(["--columns", ",a,,b,"]).unwrap();
+        assert_eq!(res.values_of("columns").unwrap(), ["", "a", "", "b"]);
+
+        let res = parser.try_get_matches_from(["--sizes", "1KB:huge"]);
+        assert!(matches!(res, Err(ParseError::Value { kind: ValueErrorKind::Type("a byte size"), .. })));
+        let res = parser.try_get_matches_from(["-F", "a", "-F", "b"]);
+        assert_eq!(res, Err(ParseError::Duplicate(String::from("b"))));
+    }
+
+    #[test]
+    fn test_to_command_line() {
+        assert_eq!(shell_escape("a.txt"), "a.txt");
+        assert_eq!(shell_escape("a b"), "'a b'");
+        assert_eq!(shell_escape("it's"), r"'it'\''s'");
+        assert_eq!(shell_escape(""), "''");
+        assert_eq!(shell_escape("--"), "'--'");
+
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().param("src"),
+            Arg::new().param("dest"),
+            Arg::new().input("msg").short('m'),
+            Arg::new().input("mode").default_with(|| String::from("fast")),
+            Arg::new().flag("verbose").short('v').count(),
+            Arg::new().flag("all").implies(&["color"]),
+            Arg::new().flag("color"),
+            Arg::new().input("define").short('D').defines(),
+            Arg::new().input("features").value_delimiter(',').keep_empty_values(),
+            Arg::new().input("files").values_until_terminator(),
+        ]);
+        parser.trailing_var_arg("rest");
+
+        let line = r#"-vv "my file" --msg "say \"hi\" it's -1" -D b=2 -D "a=x y" --features a, --all --files -- "--" -- -dest -- x 'y z'"#;
+        let res = parser.parse_str(line).unwrap();
+        assert_eq!(res.to_command_line(), [
+            r#"--msg 'say "hi" it'\''s -1' --mode fast --verbose --verbose --all --color"#,
+            "--define 'a=x y' --define b=2 --features a --files --",
+            "'my file' '--' -- -dest '--' x 'y z'",
+        ].join(" "));
+        assert_eq!(parser.parse_str(&res.to_command_line()), Ok(res));
+
+        // Values which look like options are attached, and such positionals come after a terminator
+        let parser = Parser::new();
+        parser.add_args(vec![Arg::new().param("n"), Arg::new().input("offset"), Arg::new().flag("plain").short('p')]);
+        parser.plus_flags(true);
+        let res = parser.try_get_matches_from(["--offset=-5", "-p", "--", "-3"]).unwrap();
+        assert_eq!(res.to_command_line(), "--offset=-5 -plain -- -3");
+        let reparsed = parser.parse_str(&res.to_command_line()).unwrap();
+        assert_eq!(reparsed.value_of("offset"), Some("-5"));
+        assert_eq!(reparsed.value_of("n"), Some("-3"));
+        assert!(!reparsed.get_flag("plain"));
+        assert_eq!(reparsed, res);
+
+        // Toggled flags are written back with `+` or `-`, so they parse to the same values
+        let res = parser.try_get_matches_from(["+plain", "5"]).unwrap();
+        assert_eq!(res.to_command_line(), "+plain 5");
+        assert_eq!(parser.parse_str(&res.to_command_line()), Ok(res));
+        let res = parser.try_get_matches_from(["--plain", "5"]).unwrap();
+        assert_eq!(res.to_command_line(), "--plain 5");
+        assert_eq!(parser.parse_str(&res.to_command_line()), Ok(res));
+    }
+
+    #[test]
+    fn test_env_flag() {
+        let env = Rc::new(RefCell::new(HashMap::new()));
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("ci").env_flag("CI").implies(&["color"]),
+            Arg::new().flag("color"),
+            Arg::new().flag("debug").env_flag("DEBUG"),
+        ]);
+        let source = Rc::clone(&env);
+        parser.env_source(move |var| source.borrow().get(var).cloned());
+
+        let res = parser.try_get_matches_from(Vec::<String>::new()).unwrap();
+        assert!(!res.is_present("ci"));
+
+        env.borrow_mut().insert("CI", String::from("TRUE"));
+        env.borrow_mut().insert("DEBUG", String::from("0"));
+        let res = parser.try_get_matches_from(Vec::<String>::new()).unwrap();
+        assert!(res.is_present("ci"));
+        assert!(res.is_present("color"));
+        assert!(!res.is_present("debug"));
+
+        for value in ["1", "yes"] {
+            env.borrow_mut().insert("DEBUG", String::from(value));
+            assert!(parser.try_get_matches_from(Vec::<String>::new()).unwrap().is_present("debug"));
         }
-        assert_eq!(short.expecting, true);
 
-        let flag = Arg::new().flag("flag").short( 'f');
-        assert_eq!(flag.expecting, false);
-        if let ArgTypes::Short(c) = flag.arg_type {
-            assert_eq!(c, 'f');
-        } else {
-            assert!(false);
+        // The command line wins
+        parser.plus_flags(true);
+        let res = parser.try_get_matches_from(["-ci"]).unwrap();
+        assert!(!res.get_flag("ci"));
+        assert!(!res.is_present("color"));
+        let res = parser.try_get_matches_from(["--ci"]).unwrap();
+        assert!(res.get_flag("ci"));
+
+        // A source can reconfigure the parser it's reading for without a panic
+        let parser = Rc::new(parser);
+        let inner = Rc::downgrade(&parser);
+        parser.env_source(move |_| {
+            inner.upgrade().unwrap().env_source(|_| None);
+            Some(String::from("1"))
+        });
+        assert!(parser.try_get_matches_from(Vec::<String>::new()).unwrap().is_present("ci"));
+        assert!(!parser.try_get_matches_from(Vec::<String>::new()).unwrap().is_present("ci"));
+    }
+
+    #[test]
+    fn test_parse_never_panics() {
+        let pool = [
+            "-", "--", "---", "-o", "-o=", "--out", "--out=", "--=", "=", "-vvo", "-vo=x", "-x", "+v", "-verbose",
+            "a", "-5", "-3.", "--point", "1", "'", "\"", "é", "-é", "--é=é", "k=v", "--define", "-D", "--nope", "--nope=1",
+            "--files", "--list", "a,,b,", "", " ",
+        ];
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("out").short('o').non_empty(),
+            Arg::new().flag("verbose").short('v').count(),
+            Arg::new().flag("point").takes_positionals(2),
+            Arg::new().input("define").short('D').defines(),
+            Arg::new().input("files").values_until_terminator(),
+            Arg::new().input("list").value_delimiter(','),
+            Arg::new().input("size").byte_size().take_next_raw(),
+            Arg::new().param("src"),
+            Arg::new().param("dest"),
+        ]);
+        parser.catch_all("extra");
+        parser.trailing_var_arg("rest");
+
+        // A fixed seed keeps the test reproducible
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = |n: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % n as u64) as usize
+        };
+        for round in 0..2000 {
+            parser.plus_flags(round % 2 == 0);
+            parser.parse_interspersed(round % 3 != 0);
+            parser.on_duplicate(if round % 5 == 0 { DuplicatePolicy::LastWins } else { DuplicatePolicy::Error });
+
+            let len = next(8);
+            let tokens: Vec<String> = (0..len).map(|_| String::from(pool[next(pool.len())])).collect();
+            let _ = parser.try_get_matches_from(tokens.clone());
+            let _ = parser.parse_with_warnings(tokens.clone());
+            let _ = parser.parse(&mut tokens.clone().into_iter());
+            let _ = parser.parse_str(&tokens.join(" "));
+            let _ = parser.explain(tokens.clone());
+            for token in &tokens {
+                let _ = parser.complete(token);
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_parsers_never_panic() {
+        let names = ["a", "b", "out", "x", "", "a=b", "a.b", "-", "é"];
+        let pool = [
+            "-", "--", "---", "-a", "-b", "-o", "-x", "-o=", "--out", "--out=", "--a=1", "--=", "=", "-abo", "-ax=y", "+a",
+            "a", "b", "p", "q", "END", "-5", "1", "'", "\"", "\\", "é", "-é", "--é=é", "k=v", "out=1", "-Xa", "-Xout=",
+            "-Xb=1", "-Xx", "--nope", "--nope=1", "a,,b,", "", " ",
+        ];
+
+        // A fixed seed keeps the test reproducible
+        let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut next = |n: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % n as u64) as usize
+        };
+        for _ in 0..3000 {
+            let parser = Parser::new();
+            for _ in 0..next(5) {
+                let name = names[next(names.len())];
+                let other = names[next(names.len())];
+                let arg = match next(26) {
+                    0 => Arg::new().flag(name),
+                    1 => Arg::new().flag(name).count(),
+                    2 => Arg::new().input(name).non_empty(),
+                    3 => Arg::new().flag(name).takes_positionals(1 + next(2)),
+                    4 => Arg::new().input(name).defines().define_schema(&[("k", DefineKind::Int)]).deny_unknown_keys(),
+                    5 => Arg::new().input(name).values_until_terminator(),
+                    6 => Arg::new().input(name).terminator(["END", ""][next(2)]),
+                    7 => Arg::new().input(name).value_delimiter([',', '='][next(2)]),
+                    8 => Arg::new().input(name).take_next_raw(),
+                    9 | 10 => Arg::new().param(name),
+                    11 => Arg::new().flag(name).prefix(["-X", "", "--"][next(3)]),
+                    12 => Arg::new().long(name).toggle(&["p", "q"], "p"),
+                    13 => Arg::new().input(name).byte_size(),
+                    14 => Arg::new().input(name).duration(),
+                    15 => Arg::new().flag(name).disables_validation(),
+                    16 => Arg::new().input(name).default_with(|| String::from("1")),
+                    17 => Arg::new().flag(name).auto_short(),
+                    18 => Arg::new().short(name.chars().next().unwrap_or('a')),
+                    19 => Arg::new().input(name).short(name.chars().next().unwrap_or('a')),
+                    20 => Arg::new().flag(name).alias(other),
+                    21 => Arg::new().flag(name).implies(&[other]),
+                    22 => Arg::new().input(name).required(true),
+                    23 => Arg::new().input(name).requires_any(&[other]),
+                    24 => Arg::new().input(name).required_unless(other),
+                    _ => Arg::new().input(name).id(other),
+                };
+                parser.add_arg(arg);
+            }
+            parser.plus_flags(next(2) == 0);
+            parser.parse_interspersed(next(3) != 0);
+            parser.on_duplicate([DuplicatePolicy::Error, DuplicatePolicy::FirstWins, DuplicatePolicy::LastWins][next(3)]);
+            parser.strict_ordering(next(4) == 0);
+            parser.require_params(next(3) != 0);
+            parser.allow_negative_numbers(next(2) == 0);
+            parser.param_equals_as_value(next(3) == 0);
+            parser.catch_all_mode([CatchAllMode::NextValue, CatchAllMode::Flag][next(2)]);
+            parser.usage_width(next(20));
+            if next(3) == 0 {
+                parser.catch_all("extra");
+            }
+            if next(3) == 0 {
+                parser.trailing_var_arg("rest");
+            }
+            if next(6) == 0 {
+                parser.max_args(next(6));
+            }
+            if next(6) == 0 {
+                let _ = parser.mode(&["a", ""]);
+            }
+            if next(4) == 0 {
+                parser.exclusive_group(&[names[next(4)], names[next(4)]], next(2) == 0);
+            }
+            if next(4) == 0 {
+                parser.group_all_or_none(&[names[next(4)], names[next(4)]]);
+            }
+
+            let len = next(12);
+            let tokens: Vec<String> = (0..len).map(|_| String::from(pool[next(pool.len())])).collect();
+            let _ = parser.validate();
+            let _ = (parser.usage(), parser.usage_verbose(), parser.usage_line(), parser.required_args());
+            let _ = parser.find_conflicts(&["a", "b", "out"]);
+            if let Ok(output) = parser.try_get_matches_from(tokens.clone()) {
+                let _ = parser.parse_str(&output.to_command_line());
+                #[cfg(feature = "serde")]
+                let _ = output.to_json();
+            }
+            let _ = parser.parse_str(&tokens.join(" "));
+            let _ = parser.parse_str(&tokens.concat().chars().rev().collect::<String>());
+            let _ = parser.parse_ordered(tokens.clone());
+            let _ = parser.parse_until_positional(tokens.clone());
+            let _ = parser.parse_peekable(&mut tokens.clone().into_iter().peekable());
+            let _ = parser.parse_with_warnings(tokens.clone());
+            let _ = parser.explain(tokens.clone());
+            let map: HashMap<String, String> = tokens.iter()
+                .filter_map(|token| token.split_once('='))
+                .map(|(k, v)| (String::from(k), String::from(v)))
+                .collect();
+            let _ = parser.parse_map(&map);
+            for token in &tokens {
+                let _ = parser.complete(token);
+            }
         }
-        assert_eq!(flag.name, "flag");
+    }
 
-        let mut param = Arg::new().param("param");
-        assert_eq!(param.expecting, false);
-        if let ArgTypes::Param(used) = param.arg_type {
-            assert!(!used);
-        } else {
-            assert!(false);
-        }
-        assert_eq!(param.name, "param");
+    #[test]
+    fn test_input_value() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("input").short('i'),
+            Arg::new().param("file"),
+        ]);
 
-        param.set_used(true);
-        if let ArgTypes::Param(used) = param.arg_type {
-            assert!(used);
-        } else {
-            assert!(false);
-        }
+        let res = parser.try_get_matches_from(["--input", "value", "a.txt"]).unwrap();
+        assert_eq!(res.value_of("input"), Some("value"));
+        assert_eq!(res.value_of("file"), Some("a.txt"));
+
+        // The value is only taken once, so the next token is a positional again
+        let res = parser.try_get_matches_from(["a.txt", "-i", "value"]).unwrap();
+        assert_eq!(res.value_of("input"), Some("value"));
+        assert_eq!(res.value_of("file"), Some("a.txt"));
+        let res = parser.try_get_matches_from(["-i", "value", "a.txt", "b.txt"]);
+        assert_eq!(res, Err(ParseError::TooManyPositionals { expected: 1, got: 2 }));
+
+        let res = parser.try_get_matches_from(["a.txt", "--input"]);
+        assert_eq!(res, Err(ParseError::MissingValue(String::from("input"))));
     }
 
     #[test]
-    fn append_args() {
-        let default = Arg::new().input("default");
-        let short = Arg::new().input("short").short('s');
-        let flag = Arg::new().flag("flag").short( 'f');
+    fn test_param_equals_as_value() {
+        let parser = Parser::new();
+        parser.add_args(vec![Arg::new().param("file"), Arg::new().param("mode"), Arg::new().flag("verbose")]);
+
+        // Kept literal by default
+        let res = parser.try_get_matches_from(["mode=fast", "a.txt"]).unwrap();
+        assert_eq!(res.value_of("file"), Some("mode=fast"));
+        assert_eq!(res.value_of("mode"), Some("a.txt"));
 
+        parser.param_equals_as_value(true);
+        let res = parser.try_get_matches_from(["mode=fast", "--verbose", "a.txt"]).unwrap();
+        assert_eq!(res.value_of("file"), Some("a.txt"));
+        assert_eq!(res.value_of("mode"), Some("fast"));
+        let res = parser.try_get_matches_from(["file=a=b.txt", "mode="]).unwrap();
+        assert_eq!(res.value_of("file"), Some("a=b.txt"));
+        assert_eq!(res.value_of("mode"), Some(""));
+
+        // Unknown names are plain positionals
+        let res = parser.try_get_matches_from(["key=value", "mode=fast"]).unwrap();
+        assert_eq!(res.value_of("file"), Some("key=value"));
+
+        let res = parser.try_get_matches_from(["a.txt", "file=b.txt"]);
+        assert_eq!(res, Err(ParseError::Duplicate(String::from("file=b.txt"))));
+
+        let explained = parser.explain(["mode=fast", "a.txt"]);
+        assert_eq!(explained[0].1, Classification::Positional(String::from("mode")));
+        assert_eq!(explained[1].1, Classification::Positional(String::from("file")));
+    }
+
+    #[test]
+    fn test_max_args() {
         let parser = Parser::new();
-        parser.add_arg(default);
-        assert_eq!(parser.len(), 1);
-        parser.add_args(vec![short, flag]);
-        assert_eq!(parser.len(), 3);
-        assert_eq!(parser.args()[2].name, "flag");
+        parser.add_args(vec![Arg::new().flag("verbose").short('v').count(), Arg::new().input("out")]);
+        parser.max_args(3);
+
+        assert!(parser.try_get_matches_from(["-v", "--out", "a.txt"]).is_ok());
+        let res = parser.try_get_matches_from(["-v", "-v", "--out", "a.txt"]);
+        assert_eq!(res, Err(ParseError::TooManyArgs { limit: 3 }));
+        assert_eq!(res.unwrap_err().to_string(), "Invalid command, more than 3 arguments");
+        assert!(parser.parse_str("-v -v -v -v").is_err());
+        assert!(parser.parse_with_warnings(["-v"; 4]).is_err());
+
+        // Endless input is cut off at the limit
+        let mut endless = std::iter::repeat(String::from("-v"));
+        let err = parser.parse(&mut endless).unwrap_err();
+        assert_eq!(err.downcast_ref::<ParseError>(), Some(&ParseError::TooManyArgs { limit: 3 }));
+
+
+        // No limit by default
+        let parser = Parser::new();
+        parser.add_arg(Arg::new().flag("verbose").short('v').count());
+        assert_eq!(parser.try_get_matches_from(["-v"; 100]).unwrap().count("verbose"), 100);
     }
 
     #[test]
-    fn test_parse_err() {
-        let default = Arg::new().input("default");
-        let short = Arg::new().input("short").short('s');
-        let flag = Arg::new().flag("flag").short( 'f');
-        let param1 = Arg::new().param("p1");
-        let param2 = Arg::new().param("p2");
+    fn test_hide_default_value() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("mode").help("How to run").default_value("fast"),
+            Arg::new().input("cache").default_value("/home/me/.cache/tool").hide_default_value(),
+            Arg::new().input("level").help("Log level"),
+            Arg::new().input("user").hide_default_value(),
+        ]);
+        parser.with_defaults_from(HashMap::from([(String::from("user"), String::from("me"))]));
+
+        assert_eq!(parser.usage(), [
+            "Options:",
+            "      --mode <mode>    How to run [default: fast]",
+            "      --cache <cache>",
+            "      --level <level>  Log level",
+            "      --user <user>",
+            "",
+        ].join("\n"));
+
+        let res = parser.try_get_matches_from(Vec::<String>::new()).unwrap();
+        assert_eq!(res.value_of("cache"), Some("/home/me/.cache/tool"));
+        assert_eq!(res.value_of("user"), Some("me"));
 
         let parser = Parser::new();
-        parser.add_args(vec![default, short, flag, param1, param2]);
+        parser.add_arg(Arg::new().input("mode").default_value("fast"));
+        assert_eq!(parser.usage(), "Options:\n      --mode <mode>  [default: fast]\n");
 
-        // Tests err on duplicate value
-        let mut cmd = "--short short_inp -s s_inp p1 p2"
-            .split_whitespace()
-            .map(|s| { String::from(s) });
+        // A lazy default isn't computed just to render help
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let parser = Parser::new();
+        parser.add_arg(Arg::new().input("jobs").default_with(move || {
+            counter.set(counter.get() + 1);
+            String::from("8")
+        }));
+        assert_eq!(parser.usage(), "Options:\n      --jobs <jobs>\n");
+        assert_eq!(parser.usage_verbose(), "Options:\n      --jobs <jobs>\n");
+        assert_eq!(calls.get(), 0);
+        assert_eq!(parser.try_get_matches_from(Vec::<String>::new()).unwrap().value_of("jobs"), Some("8"));
+        assert_eq!(calls.get(), 1);
+    }
 
-        let res = parser.parse(&mut cmd);
-        assert!(res.is_err());
-        
-        // Tests err on invalid command order
-        let mut cmd = "--default -f p1 p2"
-            .split_whitespace()
-            .map(|s| { String::from(s) });
 
-        let res = parser.parse(&mut cmd);
-        assert!(res.is_err());
+    #[test]
+    fn test_parse_ordered() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("set").short('s'),
+            Arg::new().input("tags").value_delimiter(','),
+            Arg::new().flag("quiet").short('q'),
+            Arg::new().param("file"),
+        ]);
+        parser.on_duplicate(DuplicatePolicy::LastWins);
 
-        // Tests err on unexpected token
-        let mut cmd = "p1 p2 -f dsjfhkjuh"
-        .split_whitespace()
-        .map(|s| { String::from(s) });
+        let ordered = parser.parse_ordered(["--set", "a", "file.txt", "--tags=x,y", "-s", "b", "-q"]).unwrap();
+        assert_eq!(ordered, [
+            (String::from("set"), Value::Str(String::from("a"))),
+            (String::from("file"), Value::Str(String::from("file.txt"))),
+            (String::from("tags"), Value::Multi(vec![String::from("x"), String::from("y")])),
+            (String::from("set"), Value::Str(String::from("b"))),
+            (String::from("quiet"), Value::Flag),
+        ]);
 
-        let res = parser.parse(&mut cmd);
-        assert!(res.is_err());
+        // Later duplicates are dropped when only the first counts
+        parser.on_duplicate(DuplicatePolicy::FirstWins);
+        let ordered = parser.parse_ordered(["-s", "a", "-s", "b", "f"]).unwrap();
+        assert_eq!(ordered, [
+            (String::from("set"), Value::Str(String::from("a"))),
+            (String::from("file"), Value::Str(String::from("f"))),
+        ]);
 
-        // Tests err on missing token
-        let mut cmd = "p1 p2 --default"
-            .split_whitespace()
-            .map(|s| { String::from(s) });
+        assert_eq!(parser.parse_ordered(["f", "-s"]), Err(ParseError::MissingValue(String::from("set"))));
+    }
 
-        let res = parser.parse(&mut cmd);
-        assert!(res.is_err());
 
-        // Tests err on missing param
-        let mut cmd = "p1 -f"
-            .split_whitespace()
-            .map(|s| { String::from(s) });
+    #[test]
+    fn test_exclusive_group() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("json"),
+            Arg::new().flag("yaml"),
+            Arg::new().input("format").default_with(|| String::from("text")),
+            Arg::new().flag("quiet"),
+            Arg::new().flag("loud"),
+        ]);
+        parser.exclusive_group(&["json", "yaml", "format"], true);
+        parser.exclusive_group(&["quiet", "loud"], false);
+        let members = vec![String::from("json"), String::from("yaml"), String::from("format")];
 
-        let res = parser.parse(&mut cmd);
-        assert!(res.is_err());
+        // The default of a member doesn't select it
+        assert_eq!(parser.try_get_matches_from(Vec::<String>::new()), Err(ParseError::GroupViolation {
+            members: members.clone(),
+            reason: GroupViolationKind::NoneSelected,
+        }));
+        assert_eq!(parser.try_get_matches_from(["--json", "--format", "csv"]), Err(ParseError::GroupViolation {
+            members: members.clone(),
+            reason: GroupViolationKind::TooMany,
+        }));
+        let err = parser.try_get_matches_from(["--json", "--quiet", "--loud"]).unwrap_err();
+        assert_eq!(err, ParseError::GroupViolation {
+            members: vec![String::from("quiet"), String::from("loud")],
+            reason: GroupViolationKind::TooMany,
+        });
+        assert_eq!(err.to_string(), "Invalid command, only one of 'quiet', 'loud' can be given");
 
-        // Tests err on unrecognized arg
-        let mut cmd = "p1 p2 --doesntexist"
-            .split_whitespace()
-            .map(|s| { String::from(s) });
+        let output = parser.try_get_matches_from(["--yaml"]).unwrap();
+        assert!(output.is_present("yaml"));
+        assert_eq!(output.value_of("format"), Some("text"));
+        assert_eq!(
+            ParseError::GroupViolation { members, reason: GroupViolationKind::NoneSelected }.to_string(),
+            "Invalid command, one of 'json', 'yaml', 'format' is required",
+        );
+
+        // Members have to be registered arguments
+        assert_eq!(parser.validate(), Ok(()));
+        parser.exclusive_group(&["quiet", "silent"], false);
+        let err = parser.validate().unwrap_err();
+        assert_eq!(err, ConfigError::UnknownMember {
+            group: vec![String::from("quiet"), String::from("silent")],
+            member: String::from("silent"),
+        });
+        assert_eq!(err.to_string(), "Invalid parser, the group 'quiet', 'silent' refers to unknown argument 'silent'");
+    }
 
-        let res = parser.parse(&mut cmd);
-        assert!(res.is_err());
 
+    #[test]
+    fn test_arg_prefix() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("lint").prefix("-X"),
+            Arg::new().input("ms").prefix("-X"),
+            Arg::new().flag("verbose").short('v'),
+        ]);
+
+        let output = parser.try_get_matches_from(["-Xlint", "-v", "-Xms", "512m"]).unwrap();
+        assert!(output.is_present("lint"));
+        assert!(output.is_present("verbose"));
+        assert_eq!(output.value_of("ms"), Some("512m"));
+        assert_eq!(parser.try_get_matches_from(["-Xms=1g"]).unwrap().value_of("ms"), Some("1g"));
+
+        // Only the arg's own prefix calls it
+        assert_eq!(parser.try_get_matches_from(["--lint"]), Err(ParseError::Unexpected(String::from("--lint"))));
+        assert_eq!(parser.try_get_matches_from(["-Xverbose"]), Err(ParseError::Unexpected(String::from("-Xverbose"))));
+        assert_eq!(parser.complete("--"), vec!["--verbose"]);
+
+        assert!(parser.usage().contains("    -Xms <ms>"));
+        assert_eq!(output.to_command_line(), "-Xlint -Xms 512m --verbose");
     }
 
+
     #[test]
-    pub fn test_parse_success() {
-        let default = Arg::new().input("default");
-        let short = Arg::new().input("short").short('s');
-        let flag = Arg::new().flag("flag").short( 'f');
-        let param1 = Arg::new().param("file");
-        let param2 = Arg::new().param("path");
+    fn test_find_conflicts() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("json"),
+            Arg::new().flag("yaml"),
+            Arg::new().flag("toml"),
+            Arg::new().flag("quiet"),
+            Arg::new().flag("verbose"),
+        ]);
+        parser.exclusive_group(&["json", "yaml", "toml"], false);
+        parser.exclusive_group(&["quiet", "verbose"], false);
+        parser.exclusive_group(&["quiet", "json"], false);
+        let pair = |a: &str, b: &str| (String::from(a), String::from(b));
+
+        assert!(parser.find_conflicts(&[]).is_empty());
+        assert!(parser.find_conflicts(&["json", "verbose"]).is_empty());
+        assert_eq!(parser.find_conflicts(&["toml", "json"]), vec![pair("json", "toml")]);
+        assert_eq!(parser.find_conflicts(&["json", "yaml", "toml", "quiet"]), vec![
+            pair("json", "yaml"),
+            pair("json", "toml"),
+            pair("yaml", "toml"),
+            pair("quiet", "json"),
+        ]);
+        assert_eq!(parser.find_conflicts(&["verbose", "quiet", "unknown"]), vec![pair("quiet", "verbose")]);
+    }
+
 
+    #[test]
+    fn test_parse_str_keeps_spaces() {
         let parser = Parser::new();
-        parser.add_args(vec![default, short, flag, param1, param2]);
+        parser.add_args(vec![
+            Arg::new().input("msg").short('m'),
+            Arg::new().input("sep"),
+            Arg::new().param("text"),
+        ]);
 
-        let mut cmd = "--default def_arg filename -s s_arg -f pathname"
-            .split_whitespace()
-            .map(|s| { String::from(s) });
+        let output = parser.parse_str(r#"-m "  spaced  " --sep=' ' "  two  words ""#).unwrap();
+        assert_eq!(output.value_of("msg"), Some("  spaced  "));
+        assert_eq!(output.value_of("sep"), Some(" "));
+        assert_eq!(output.value_of("text"), Some("  two  words "));
 
-        let res = parser.parse(&mut cmd);
-        assert!(res.is_ok());
+        // Unquoted whitespace only separates tokens
+        let output = parser.parse_str("   -m   a   b   ").unwrap();
+        assert_eq!(output.value_of("msg"), Some("a"));
+        assert_eq!(output.value_of("text"), Some("b"));
+    }
 
-        let res = res.unwrap();
-        assert_eq!(res.len(), 5);
-        assert!(res.contains_key("default"));
-        assert_eq!(res.get("default").unwrap(), &Some(String::from("def_arg")));
-        assert!(res.contains_key("short"));
-        assert_eq!(res.get("short").unwrap(), &Some(String::from("s_arg")));
-        assert!(res.contains_key("flag"));
-        assert_eq!(res.get("flag").unwrap(), &None);
-        assert!(res.contains_key("file"));
-        assert_eq!(res.get("file").unwrap(), &Some(String::from("filename")));
-        assert!(res.contains_key("path"));
-        assert_eq!(res.get("path").unwrap(), &Some(String::from("pathname")));
+
+    #[test]
+    fn test_group_all_or_none() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("username").short('u'),
+            Arg::new().input("password").short('p'),
+            Arg::new().input("host").default_with(|| String::from("localhost")),
+            Arg::new().flag("verbose"),
+        ]);
+        parser.group_all_or_none(&["username", "password", "host"]);
+        let group = vec![String::from("username"), String::from("password"), String::from("host")];
+
+        assert!(parser.try_get_matches_from(["--verbose"]).is_ok());
+        let output = parser.try_get_matches_from(["-u", "me", "-p", "pw", "--host", "db"]).unwrap();
+        assert_eq!(output.value_of("host"), Some("db"));
+
+        // A default doesn't complete the group
+        let err = parser.try_get_matches_from(["-u", "me", "-p", "pw"]).unwrap_err();
+        assert_eq!(err, ParseError::PartialGroup {
+            group: group.clone(),
+            present: vec![String::from("username"), String::from("password")],
+            missing: vec![String::from("host")],
+        });
+        assert_eq!(err.to_string(), "Invalid command, 'username', 'password' must be given with 'host'");
+        assert_eq!(parser.try_get_matches_from(["--host", "db"]), Err(ParseError::PartialGroup {
+            group,
+            present: vec![String::from("host")],
+            missing: vec![String::from("username"), String::from("password")],
+        }));
+
+        // Members have to be registered arguments
+        assert_eq!(parser.validate(), Ok(()));
+        parser.group_all_or_none(&["user", "password"]);
+        assert_eq!(parser.validate(), Err(ConfigError::UnknownMember {
+            group: vec![String::from("user"), String::from("password")],
+            member: String::from("user"),
+        }));
+    }
+
+
+    #[test]
+    fn test_parse_stats() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("verbose").short('v').count(),
+            Arg::new().input("out").short('o'),
+            Arg::new().param("a"),
+            Arg::new().param("b"),
+            Arg::new().param("c"),
+        ]);
+        parser.catch_all("extra");
+
+        let output = parser.try_get_matches_from(["one", "--out", "x.txt", "two", "-v", "three"]).unwrap();
+        assert_eq!(output.stats(), ParseStats { tokens: 6, options: 2, positionals: 3, unknowns: 0 });
+
+        let output = parser.try_get_matches_from(["-vvo", "x.txt", "--nope=1", "--what", "1", "2", "3", "4"]).unwrap();
+        assert_eq!(output.stats(), ParseStats { tokens: 8, options: 3, positionals: 3, unknowns: 2 });
+
+        // Stats don't take part in comparing outputs
+        let spread = parser.try_get_matches_from(["-v", "-v", "-o", "x.txt", "--nope=1", "--what=1", "2", "3", "4"]).unwrap();
+        assert_eq!(spread, output);
+        assert_ne!(spread.stats(), output.stats());
+    }
+
+
+    #[test]
+    fn test_matched_form() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("verbose").short('v').count(),
+            Arg::new().flag("all").short('a'),
+            Arg::new().input("out").short('o').alias("output"),
+            Arg::new().flag("color").short('c'),
+            Arg::new().flag("lint").prefix("-X"),
+            Arg::new().param("file"),
+        ]);
+        parser.plus_flags(true);
+
+        let output = parser.try_get_matches_from(["--verbose", "--output=a.txt", "+c", "-Xlint", "f"]).unwrap();
+        assert_eq!(output.matched_form("verbose"), Some(MatchedForm::Long));
+        assert_eq!(output.matched_form("out"), Some(MatchedForm::Alias(String::from("output"))));
+        assert_eq!(output.matched_form("color"), Some(MatchedForm::Short('c')));
+        assert_eq!(output.matched_form("lint"), Some(MatchedForm::Long));
+        assert_eq!(output.matched_form("file"), None);
+        assert_eq!(output.matched_form("all"), None);
+
+        // Every arg in a cluster is called by its short, and the last call wins
+        let output = parser.try_get_matches_from(["--verbose", "-avo", "a.txt", "-color", "f"]).unwrap();
+        assert_eq!(output.matched_form("verbose"), Some(MatchedForm::Short('v')));
+        assert_eq!(output.matched_form("all"), Some(MatchedForm::Short('a')));
+        assert_eq!(output.matched_form("out"), Some(MatchedForm::Short('o')));
+        assert_eq!(output.matched_form("color"), Some(MatchedForm::Long));
+        assert_eq!(parser.try_get_matches_from(["-o=x", "f"]).unwrap().matched_form("out"), Some(MatchedForm::Short('o')));
+    }
+
+
+    #[test]
+    fn test_parse_until_positional() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().input("out").short('o'),
+        ]);
+
+        let (output, rest) = parser.parse_until_positional(["-v", "-o", "out", "run", "--child-flag", "-v", "--"]).unwrap();
+        assert!(output.is_present("verbose"));
+        assert_eq!(output.value_of("out"), Some("out"));
+        assert_eq!(rest, ["run", "--child-flag", "-v", "--"]);
+
+        // The terminator is dropped, and a value that looks like a positional stays with its option
+        let (output, rest) = parser.parse_until_positional(["-o", "run", "--", "-v"]).unwrap();
+        assert_eq!(output.value_of("out"), Some("run"));
+        assert!(!output.is_present("verbose"));
+        assert_eq!(rest, ["-v"]);
+
+        let (output, rest) = parser.parse_until_positional(["-v"]).unwrap();
+        assert!(output.is_present("verbose"));
+        assert!(rest.is_empty());
+
+        // Unknown options before the first positional are still errors
+        assert_eq!(parser.parse_until_positional(["--nope", "run"]), Err(ParseError::Unexpected(String::from("--nope"))));
+        // Params are left for the rest, and the split agrees with how the options are parsed
+        let parser = Parser::new();
+        parser.add_arg(Arg::new().param("cmd"));
+        parser.catch_all("extra");
+        let (output, rest) = parser.parse_until_positional(["--color", "red", "run"]).unwrap();
+        assert_eq!(output.extras()["color"], "red");
+        assert!(!output.is_present("cmd"));
+        assert_eq!(rest, ["run"]);
+
+        let parser = Parser::new();
+        parser.add_arg(Arg::new().flag("verbose").short('v'));
+        parser.mode(&["build", "test"]).unwrap();
+        let (output, rest) = parser.parse_until_positional(["build", "-v", "child"]).unwrap();
+        assert_eq!(output.value_of("mode"), Some("build"));
+        assert!(output.is_present("verbose"));
+        assert_eq!(rest, ["child"]);
+    }
+
+
+    #[test]
+    fn test_usage_line() {
+        let parser = Parser::new();
+        parser.bin_name("prog");
+        assert_eq!(parser.usage_line(), "Usage: prog");
+
+        parser.add_arg(Arg::new().param("file"));
+        assert_eq!(parser.usage_line(), "Usage: prog <file>");
+
+        parser.add_args(vec![Arg::new().flag("verbose").short('v'), Arg::new().input("out"), Arg::new().param("dest")]);
+        assert_eq!(parser.usage_line(), "Usage: prog [OPTIONS] <file> <dest>");
+
+        parser.trailing_var_arg("args");
+        assert_eq!(parser.usage_line(), "Usage: prog [OPTIONS] <file> <dest> [-- <args>...]");
+
+        parser.require_params(false);
+        assert_eq!(parser.usage_line(), "Usage: prog [OPTIONS] [file] [dest] [-- <args>...]");
+
+        // Without a name, the executable's file name is used
+        let parser = Parser::new();
+        parser.add_arg(Arg::new().flag("verbose"));
+        let line = parser.usage_line();
+        assert!(line.starts_with("Usage: simple_cli_parser") && line.ends_with(" [OPTIONS]"));
+    }
+
+
+    #[test]
+    fn test_parse_peekable() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().input("out").short('o'),
+        ]);
+        let mut args = ["-v", "-o", "run", "task", "--verbose", "--", "x"].map(String::from).into_iter().peekable();
+
+        // The value of an option is taken even when it looks like a positional
+        let hashmap = parser.parse_peekable(&mut args).unwrap();
+        assert_eq!(hashmap.get("out"), Some(&Some(String::from("run"))));
+        assert!(hashmap.contains_key("verbose"));
+        assert_eq!(args.next().as_deref(), Some("task"));
+
+        // Parsing again picks up where the last parse stopped, and the terminator is consumed
+        let hashmap = parser.parse_peekable(&mut args).unwrap();
+        assert!(hashmap.contains_key("verbose"));
+        assert!(!hashmap.contains_key("out"));
+        assert_eq!(args.collect::<Vec<String>>(), ["x"]);
+
+        let mut args = ["--nope"].map(String::from).into_iter().peekable();
+        assert!(parser.parse_peekable(&mut args).is_err());
+        assert!(args.peek().is_none());
+
+        // Params are filled before stopping
+        parser.add_arg(Arg::new().param("file"));
+        let mut args = ["-v", "a.txt", "b.txt"].map(String::from).into_iter().peekable();
+        let hashmap = parser.parse_peekable(&mut args).unwrap();
+        assert_eq!(hashmap.get("file"), Some(&Some(String::from("a.txt"))));
+        assert!(hashmap.contains_key("verbose"));
+        assert_eq!(args.collect::<Vec<String>>(), ["b.txt"]);
+
+        // Only the tokens within the limit are taken
+        parser.max_args(2);
+        let mut args = ["-v", "a.txt", "-o", "out", "b.txt"].map(String::from).into_iter().peekable();
+        let err = parser.parse_peekable(&mut args).unwrap_err();
+        assert_eq!(err.downcast_ref::<ParseError>(), Some(&ParseError::TooManyArgs { limit: 2 }));
+        assert_eq!(args.collect::<Vec<String>>(), ["-o", "out", "b.txt"]);
+    }
+
+
+    #[test]
+    fn test_mode() {
+        let parser = Parser::new();
+        parser.add_args(vec![Arg::new().flag("release").short('r'), Arg::new().param("target")]);
+        parser.mode(&["build", "test", "run"]).unwrap();
+        let allowed = vec![String::from("build"), String::from("test"), String::from("run")];
+
+        let output = parser.try_get_matches_from(["test", "-r", "app"]).unwrap();
+        assert_eq!(output.value_of("mode"), Some("test"));
+        assert_eq!(output.value_of("target"), Some("app"));
+        assert!(output.is_present("release"));
+        assert_eq!(output.to_command_line(), "test --release app");
+
+        // The mode has to be the very first token
+        let err = parser.try_get_matches_from(["app", "build"]).unwrap_err();
+        assert_eq!(err, ParseError::InvalidMode { got: String::from("app"), allowed: allowed.clone() });
+        assert_eq!(err.to_string(), "Invalid command, unknown mode 'app', expected one of 'build', 'test', 'run'");
+        assert_eq!(parser.try_get_matches_from(["-r", "run", "app"]), Err(ParseError::InvalidMode {
+            got: String::from("-r"),
+            allowed,
+        }));
+        assert_eq!(parser.try_get_matches_from(Vec::<String>::new()), Err(ParseError::MissingRequired(vec![String::from("mode")])));
+
+        // A mode alone still needs the params
+        assert_eq!(parser.try_get_matches_from(["run"]), Err(ParseError::MissingRequired(vec![String::from("target")])));
+        assert_eq!(parser.parse_str("'build' app").unwrap().value_of("mode"), Some("build"));
+
+        // An argument can't be stored under the same key as the mode
+        assert_eq!(parser.validate(), Ok(()));
+        parser.add_arg(Arg::new().input("mode"));
+        assert_eq!(parser.validate(), Err(ConfigError::DuplicateKey(String::from("mode"))));
+        assert_eq!(parser.mode(&["build", "test"]), Err(ConfigError::DuplicateKey(String::from("mode"))));
+    }
+
+
+    #[test]
+    fn test_custom_terminator() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("items").short('i').terminator("END"),
+            Arg::new().flag("verbose"),
+            Arg::new().param("rest"),
+        ]);
+
+        let output = parser.try_get_matches_from(["--items", "a", "b", "c", "END", "more"]).unwrap();
+        assert_eq!(output.values_of("items").unwrap(), ["a", "b", "c"]);
+        assert_eq!(output.value_of("rest"), Some("more"));
+        assert_eq!(output.to_command_line(), "--items a b c END more");
+
+        // Options and `--` are values until the terminator
+        let output = parser.try_get_matches_from(["-i=a", "--verbose", "--", "END", "--verbose", "x"]).unwrap();
+        assert_eq!(output.values_of("items").unwrap(), ["a", "--verbose", "--"]);
+        assert!(output.is_present("verbose"));
+
+        let output = parser.parse_str("more --items 'END' END").unwrap();
+        assert_eq!(output.values_of("items").unwrap(), ["END"]);
+        assert_eq!(parser.explain(["--items", "a", "END", "more"])[2].1, Classification::Terminator);
+        assert!(parser.usage().contains("--items <items>... END"));
+    }
+
+
+    #[test]
+    fn test_required_args() {
+        let parser = Parser::new();
+        assert!(parser.required_args().is_empty());
+
+        parser.add_args(vec![
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().param("src"),
+            Arg::new().input("out").required(true),
+            Arg::new().input("mode"),
+            Arg::new().input("token").required_unless("password"),
+            Arg::new().input("password"),
+            Arg::new().param("dest"),
+        ]);
+        assert_eq!(parser.required_args(), vec!["src", "out", "token", "dest"]);
+
+        parser.require_params(false);
+        assert_eq!(parser.required_args(), vec!["out", "token"]);
+    }
+
+
+    #[test]
+    fn test_parse_reader() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().input("out").short('o'),
+            Arg::new().param("file"),
+        ]);
+
+        let stdin = b"-v --out \"my file.txt\"\n\tinput.txt\n";
+        let output = parser.parse_reader(&stdin[..]).unwrap();
+        assert!(output.is_present("verbose"));
+        assert_eq!(output.value_of("out"), Some("my file.txt"));
+        assert_eq!(output.value_of("file"), Some("input.txt"));
+
+        let err = parser.parse_reader("-x a.txt".as_bytes()).unwrap_err();
+        assert_eq!(err.downcast_ref::<ParseError>(), Some(&ParseError::Unexpected(String::from("-x"))));
+
+        // Input which isn't UTF-8 fails to read
+        let err = parser.parse_reader(&[0xff, 0xfe][..]).unwrap_err();
+        assert!(err.downcast_ref::<std::io::Error>().is_some());
+    }
+
+
+    #[test]
+    fn test_long_help() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("force").short('f').help("Overwrite files").long_help("Overwrite existing files without asking"),
+            Arg::new().input("level").help("Compression level").default_value("6"),
+            Arg::new().input("out").long_help("Where to write the archive"),
+        ]);
+
+        assert_eq!(parser.usage(), [
+            "Options:",
+            "  -f, --force          Overwrite files",
+            "      --level <level>  Compression level [default: 6]",
+            "      --out <out>",
+            "",
+        ].join("\n"));
+        assert_eq!(parser.usage_verbose(), [
+            "Options:",
+            "  -f, --force          Overwrite existing files without asking",
+            "      --level <level>  Compression level [default: 6]",
+            "      --out <out>      Where to write the archive",
+            "",
+        ].join("\n"));
+    }
+
+
+    #[test]
+    fn test_map_helpers() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().input("out").short('o'),
+            Arg::new().flag("verbose").short('v'),
+            Arg::new().flag("quiet"),
+        ]);
+        let hashmap = parser.parse(&mut ["-o", "a.txt", "-v"].map(String::from).into_iter()).unwrap();
+
+        assert_eq!(parser.value_of(&hashmap, "out"), Some("a.txt"));
+        assert!(parser.is_present(&hashmap, "out"));
+
+        // A flag is present without a value
+        assert_eq!(parser.value_of(&hashmap, "verbose"), None);
+        assert!(parser.is_present(&hashmap, "verbose"));
+
+        assert_eq!(parser.value_of(&hashmap, "quiet"), None);
+        assert!(!parser.is_present(&hashmap, "quiet"));
+        assert!(!parser.is_present(&hashmap, "unknown"));
+    }
+
+
+    #[test]
+    fn test_toggle() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().long("sort").short('s').toggle(&["asc", "desc"], "asc").help("Sort order"),
+            Arg::new().param("file"),
+        ]);
+
+        let output = parser.try_get_matches_from(["a.txt", "--sort", "desc"]).unwrap();
+        assert_eq!(output.value_of("sort"), Some("desc"));
+        assert_eq!(parser.try_get_matches_from(["-s=asc", "a.txt"]).unwrap().value_of("sort"), Some("asc"));
+
+        let err = parser.try_get_matches_from(["a.txt", "-s", "up"]).unwrap_err();
+        assert_eq!(err, ParseError::Value {
+            name: String::from("sort"),
+            value: String::from("up"),
+            kind: ValueErrorKind::NotAllowed(vec![String::from("asc"), String::from("desc")]),
+        });
+        assert_eq!(err.to_string(), "Invalid command, invalid value 'up' for 'sort': expected one of 'asc', 'desc'");
+
+        let output = parser.try_get_matches_from(["a.txt"]).unwrap();
+        assert_eq!(output.value_of("sort"), Some("asc"));
+        assert!(parser.usage().contains("-s, --sort <asc|desc>  Sort order [default: asc]"));
+    }
+
+
+    #[test]
+    fn test_num_values() {
+        let parser = Parser::new();
+        parser.add_args(vec![
+            Arg::new().flag("coord").short('c').takes_positionals(2),
+            Arg::new().flag("tag").num_values(1, usize::MAX),
+            Arg::new().flag("size").num_values(1, 3),
+            Arg::new().flag("exclude").num_values(0, usize::MAX),
+            Arg::new().flag("verbose").short('v'),
+        ]);
+
+        assert_eq!(parser.usage(), [
+            "Options:",
+            "  -c, --coord <coord> <coord>",
+            "      --tag <tag>...",
+            "      --size <size> [<size>] [<size>]",
+            "      --exclude [<exclude>...]",
+            "  -v, --verbose",
+            "",
+        ].join("\n"));
+
+        let output = parser.try_get_matches_from(["--tag", "a", "b", "c", "--size", "1", "2", "-v", "--exclude"]).unwrap();
+        assert_eq!(output.group_of("tag"), ["a", "b", "c"]);
+        assert_eq!(output.group_of("size"), ["1", "2"]);
+        assert!(output.group_of("exclude").is_empty());
+        assert!(output.is_present("exclude"));
+        assert!(output.is_present("verbose"));
+
+        // The group ends at its upper bound, and has to reach its lower one
+        assert_eq!(parser.try_get_matches_from(["--size", "1", "2", "3", "4"]), Err(ParseError::TooManyPositionals { expected: 0, got: 1 }));
+        assert_eq!(parser.try_get_matches_from(["--tag", "-v"]), Err(ParseError::MissingValue(String::from("tag"))));
+        assert_eq!(parser.try_get_matches_from(["-c", "1"]), Err(ParseError::MissingValue(String::from("coord"))));
     }
 }
\ No newline at end of file